@@ -1,18 +1,155 @@
 use kvdb::{DBOp, DBTransaction, KeyValueDB};
+use std::fmt;
 use std::sync::Arc;
 
+pub use primitive_types::H256;
+
+/// An opaque membership proof. Each algorithm serializes its own proof structure, so the proof
+/// size is the length of the inner bytes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Proof(pub Vec<u8>);
+
+impl Proof {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofError {
+    /// The algorithm (or its current configuration) can not generate or check proofs.
+    Unsupported,
+    /// The proof is malformed or does not match the given root.
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryError {
+    /// The algorithm (or its current configuration) does not keep the previous states.
+    Unsupported,
+    /// The state of the epoch is not kept, e.g. it is older than the history depth or committed
+    /// before the database is reopened.
+    Unavailable(usize),
+    /// The backend fails to read the kept state.
+    Backend(String),
+}
+
+/// A serialized elliptic curve point, in the consensus encoding of its affine form.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Commitment(pub Vec<u8>);
+
+/// The authenticated root of the state after a commit.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StateRoot {
+    /// The algorithm does not authenticate its state.
+    None,
+    /// The root hash of a Merkle Patricia Trie.
+    Hash(H256),
+    /// The commitment of the root AMT and the Merkle root of the epoch.
+    Lvmt {
+        commitment: Commitment,
+        merkle: H256,
+    },
+    /// The commitment of a single AMT.
+    Amt(Commitment),
+}
+
+impl StateRoot {
+    /// The root in the encoding required by `AuthDB::verify`.
+    pub fn proof_root(&self) -> Option<&[u8]> {
+        match self {
+            StateRoot::None => None,
+            StateRoot::Hash(hash) => Some(hash.as_bytes()),
+            StateRoot::Lvmt { commitment, .. } => Some(&commitment.0),
+            StateRoot::Amt(commitment) => Some(&commitment.0),
+        }
+    }
+}
+
+impl fmt::Display for Commitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for StateRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateRoot::None => write!(f, "none"),
+            StateRoot::Hash(hash) => write!(f, "{:x}", hash),
+            StateRoot::Lvmt { commitment, merkle } => write!(f, "{}:{:x}", commitment, merkle),
+            StateRoot::Amt(commitment) => write!(f, "{}", commitment),
+        }
+    }
+}
+
+/// A read-only handle of an `AuthDB`, which can be moved to another thread.
+pub trait AuthDBReader: Send {
+    fn get(&self, key: Vec<u8>) -> Option<Box<[u8]>>;
+}
+
 pub trait AuthDB {
     fn get(&self, key: Vec<u8>) -> Option<Box<[u8]>>;
+    /// Read a batch of keys. The results are in the same order as `keys`.
+    fn get_many(&self, keys: &[Vec<u8>]) -> Vec<Option<Box<[u8]>>> {
+        keys.iter().map(|key| self.get(key.clone())).collect()
+    }
     fn set(&mut self, key: Vec<u8>, value: Vec<u8>);
-    fn commit(&mut self, index: usize);
+    /// Remove `key` from the state. A later `get` of `key` returns `None`.
+    fn delete(&mut self, key: Vec<u8>);
+    /// Commit the updates of the epoch `index` and return the new state root.
+    fn commit(&mut self, index: usize) -> StateRoot;
+
+    /// Generate a proof for the value of `key` in the latest committed state.
+    fn prove(&mut self, _key: Vec<u8>) -> Result<Proof, ProofError> {
+        Err(ProofError::Unsupported)
+    }
+
+    /// Check `proof` against `root` and return the proven value of `key`. The encoding of `root`
+    /// is algorithm specific.
+    fn verify(
+        &self,
+        _key: Vec<u8>,
+        _proof: &Proof,
+        _root: &[u8],
+    ) -> Result<Option<Box<[u8]>>, ProofError> {
+        Err(ProofError::Unsupported)
+    }
+
+    /// Whether the states of the previous epochs can be read by `get_at`.
+    fn support_history(&self) -> bool {
+        false
+    }
+
+    /// Read `key` in the state committed at `epoch`. Only available if `support_history` returns
+    /// true.
+    fn get_at(&self, _epoch: usize, _key: Vec<u8>) -> Result<Option<Box<[u8]>>, HistoryError> {
+        Err(HistoryError::Unsupported)
+    }
+
+    /// A read-only handle for concurrent readers. Which state the handle sees while the writes of
+    /// an epoch are in progress is algorithm specific.
+    fn reader(&self) -> Option<Box<dyn AuthDBReader>> {
+        None
+    }
 
     fn flush_all(&mut self) {}
     fn backend(&self) -> Option<&dyn KeyValueDB>;
 }
 
+/// The column of the key-value store keeping the raw key-value pairs.
+pub const RAW_COL: u32 = 0;
+
 impl AuthDB for Arc<dyn KeyValueDB> {
     fn get(&self, key: Vec<u8>) -> Option<Box<[u8]>> {
-        KeyValueDB::get(&**self, 0, key.as_ref())
+        KeyValueDB::get(&**self, RAW_COL, key.as_ref())
             .unwrap()
             .map(|x| x.into_boxed_slice())
     }
@@ -20,15 +157,25 @@ impl AuthDB for Arc<dyn KeyValueDB> {
     fn set(&mut self, key: Vec<u8>, value: Vec<u8>) {
         self.write_buffered(DBTransaction {
             ops: vec![DBOp::Insert {
-                col: 0,
+                col: RAW_COL,
                 key: key.into(),
                 value,
             }],
         });
     }
 
-    fn commit(&mut self, _index: usize) {
-        self.flush().unwrap()
+    fn delete(&mut self, key: Vec<u8>) {
+        self.write_buffered(DBTransaction {
+            ops: vec![DBOp::Delete {
+                col: RAW_COL,
+                key: key.into(),
+            }],
+        });
+    }
+
+    fn commit(&mut self, _index: usize) -> StateRoot {
+        self.flush().unwrap();
+        StateRoot::None
     }
 
     fn backend(&self) -> Option<&dyn KeyValueDB> {
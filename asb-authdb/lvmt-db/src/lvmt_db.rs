@@ -1,5 +1,5 @@
 use std::collections::VecDeque;
-use std::io::Result;
+use std::io::{Error, ErrorKind, Read, Result, Write};
 use std::sync::{Arc, RwLock};
 
 use ethereum_types::H256;
@@ -19,15 +19,35 @@ use crate::crypto::{
 };
 use crate::merkle::{MerkleProof, StaticMerkleTree};
 use crate::multi_layer_amt::{
-    AMTConfig, AMTNodeIndex, EpochPosition, Key, Node, TreeName, VerInfo, VersionTree,
+    AMTConfig, AMTNodeIndex, EpochPosition, Key, Node, TreeName, VerInfo, VersionTree, SLOT_NUMBER,
 };
-use crate::serde::{MyFromBytes, MyToBytes};
+use crate::serde::{MyFromBytes, MyToBytes, SerdeType};
 use crate::storage::DBColumn;
 
 const COL_VER_TREE: u32 = 0;
 const COL_KEY_NEW: u32 = COL_VER_TREE + 1;
 const COL_MERKLE: u32 = COL_KEY_NEW + 1;
-pub const NUM_COLS: u32 = COL_MERKLE + 1;
+// The optional columns are only opened when needed, so the databases without them still work.
+const COL_SLOT_KEY: u32 = COL_MERKLE + 1;
+const COL_HISTORY: u32 = COL_SLOT_KEY + 1;
+// The keys written in each epoch, so their histories are pruned once the epoch leaves the history.
+const COL_HISTORY_INDEX: u32 = COL_HISTORY + 1;
+pub const NUM_COLS: u32 = COL_HISTORY_INDEX + 1;
+
+/// The number of columns of an `LvmtDB` keeping the slot keys for non-existence proof, or the
+/// values of the previous epochs for historical read.
+pub fn num_cols(slot_keys: bool, history: bool) -> u32 {
+    if history {
+        COL_HISTORY_INDEX + 1
+    } else if slot_keys {
+        COL_SLOT_KEY + 1
+    } else {
+        COL_MERKLE + 1
+    }
+}
+
+type History = VecDeque<(u64, Vec<u8>)>;
+type Cache = HashMap<Key, (Option<Value>, bool)>;
 
 pub static INC_KEY_LEVEL_SUM: Global<u64> = Global::INIT;
 pub static INC_KEY_COUNT: Global<u64> = Global::INIT;
@@ -40,10 +60,14 @@ pub struct LvmtDB {
     db_key: DBColumn,
     db_merkle: DBColumn,
 
-    cache: RwLock<HashMap<Key, (Option<Value>, bool)>>,
+    cache: Arc<RwLock<Cache>>,
     uncommitted_key_values: Vec<(Key, Box<[u8]>)>,
     dirty_guard: bool,
     only_merkle_root: bool,
+    // The number of the latest epochs kept for historical read.
+    history_epochs: Option<u64>,
+    // Record the keys allocated to the slots in the proof shard, for non-existence proof.
+    keep_slot_keys: bool,
 }
 
 #[derive(Default, Clone, Debug, MyFromBytes, MyToBytes)]
@@ -75,21 +99,103 @@ pub struct LevelProof {
     commitment: G1<Pairing>,
     node_fr_int: FrInt<Pairing>,
     node_version: u64,
+    // Only used in non-existence proof.
+    slot_proofs: Vec<SlotProof>,
 }
 
+// Proves the key occupying a slot, by the latest key-version-value record of the key.
 #[derive(Default)]
+pub struct SlotProof {
+    key: Vec<u8>,
+    ver_info: VerInfo,
+    value: Vec<u8>,
+    merkle_epoch: u64,
+    merkle_proof: MerkleProof,
+}
+
+#[derive(Default, MyFromBytes, MyToBytes)]
 pub struct AssociateProof {
     value: Option<Vec<u8>>,
     ver_info: VerInfo,
 }
 
+impl MyFromBytes for LevelProof {
+    fn read<R: Read>(mut reader: R, ty: SerdeType) -> Result<Self> {
+        Ok(Self {
+            merkle_epoch: MyFromBytes::read(&mut reader, ty)?,
+            merkle_proof: (
+                MyFromBytes::read_vec(&mut reader, ty)?,
+                MyFromBytes::read(&mut reader, ty)?,
+            ),
+            amt_proof: MyFromBytes::read_vec(&mut reader, ty)?,
+            commitment: MyFromBytes::read(&mut reader, ty)?,
+            node_fr_int: MyFromBytes::read(&mut reader, ty)?,
+            node_version: MyFromBytes::read(&mut reader, ty)?,
+            slot_proofs: MyFromBytes::read_vec(&mut reader, ty)?,
+        })
+    }
+}
+
+impl MyToBytes for LevelProof {
+    fn write<W: Write>(&self, mut writer: W, ty: SerdeType) -> Result<()> {
+        MyToBytes::write(&self.merkle_epoch, &mut writer, ty)?;
+        MyToBytes::write_vec(&self.merkle_proof.0, &mut writer, ty)?;
+        MyToBytes::write(&self.merkle_proof.1, &mut writer, ty)?;
+        MyToBytes::write_vec(&self.amt_proof, &mut writer, ty)?;
+        MyToBytes::write(&self.commitment, &mut writer, ty)?;
+        MyToBytes::write(&self.node_fr_int, &mut writer, ty)?;
+        MyToBytes::write(&self.node_version, &mut writer, ty)?;
+        MyToBytes::write_vec(&self.slot_proofs, &mut writer, ty)?;
+        Ok(())
+    }
+}
+
+impl MyFromBytes for SlotProof {
+    fn read<R: Read>(mut reader: R, ty: SerdeType) -> Result<Self> {
+        Ok(Self {
+            key: MyFromBytes::read(&mut reader, ty)?,
+            ver_info: MyFromBytes::read(&mut reader, ty)?,
+            value: MyFromBytes::read(&mut reader, ty)?,
+            merkle_epoch: MyFromBytes::read(&mut reader, ty)?,
+            merkle_proof: (
+                MyFromBytes::read_vec(&mut reader, ty)?,
+                MyFromBytes::read(&mut reader, ty)?,
+            ),
+        })
+    }
+}
+
+impl MyToBytes for SlotProof {
+    fn write<W: Write>(&self, mut writer: W, ty: SerdeType) -> Result<()> {
+        MyToBytes::write(&self.key, &mut writer, ty)?;
+        MyToBytes::write(&self.ver_info, &mut writer, ty)?;
+        MyToBytes::write(&self.value, &mut writer, ty)?;
+        MyToBytes::write(&self.merkle_epoch, &mut writer, ty)?;
+        MyToBytes::write_vec(&self.merkle_proof.0, &mut writer, ty)?;
+        MyToBytes::write(&self.merkle_proof.1, &mut writer, ty)?;
+        Ok(())
+    }
+}
+
+impl LevelProof {
+    pub fn commitment(&self) -> &G1<Pairing> {
+        &self.commitment
+    }
+}
+
+impl AssociateProof {
+    pub fn value(&self) -> Option<&Vec<u8>> {
+        self.value.as_ref()
+    }
+}
+
 pub type Proof = (AssociateProof, VecDeque<LevelProof>);
 pub type LvmtRoot = G1Projective;
 
 const EPOCH_NUMBER_KEY: [u8; 2] = [0, 0];
 
 impl LvmtDB {
-    // The KeyValueDB requires 3 columns.
+    // The KeyValueDB requires the columns given by `num_cols`.
     pub fn new(
         backend: Arc<dyn KeyValueDB>,
         pp: Arc<AMTParams<Pairing>>,
@@ -98,6 +204,7 @@ impl LvmtDB {
     ) -> Self {
         let db_ver_tree = DBColumn::from_kvdb(backend.clone(), COL_VER_TREE);
         let shard_node = shard_info.map(|(depth, index)| AMTNodeIndex::new(depth, index));
+        let keep_slot_keys = !only_merkle_root && shard_node.is_some();
         let version_tree = VersionTree::new(db_ver_tree, pp, shard_node);
         let db_key = DBColumn::from_kvdb(backend.clone(), COL_KEY_NEW);
         let db_merkle = DBColumn::from_kvdb(backend.clone(), COL_MERKLE);
@@ -111,9 +218,17 @@ impl LvmtDB {
             uncommitted_key_values: Vec::new(),
             dirty_guard: false,
             only_merkle_root,
+            history_epochs: None,
+            keep_slot_keys,
         }
     }
 
+    // Keep the values of the latest `epochs` epochs for historical read. The older values are
+    // pruned when their keys are written.
+    pub fn set_history_epochs(&mut self, epochs: Option<u64>) {
+        self.history_epochs = epochs;
+    }
+
     pub fn get(&self, key: &Key) -> Result<Option<Box<[u8]>>> {
         // assert!(
         //     !self.dirty_guard,
@@ -121,28 +236,57 @@ impl LvmtDB {
         // );
 
         let mut write_guard = self.cache.write().unwrap();
-        let entry = write_guard.entry(key.clone());
-
-        let maybe_value = match entry {
-            Entry::Occupied(entry) => entry.get().0.clone(),
-            Entry::Vacant(entry) => {
-                let value = self
-                    .db_key
-                    .get(key.as_ref())?
-                    .map(|x| Value::from_bytes_local(&x).unwrap());
-                entry.insert((value.clone(), false));
-                value
-            }
-        };
+        get_with_cache(&self.db_key, &mut write_guard, key)
+    }
 
-        // let ans = self
-        //     .db_key
-        //     .get(key.as_ref())?
-        //     .map(|x| Value::from_bytes_local(&x).unwrap())
-        //     .map(|x| x.value.into_boxed_slice());
-        let ans = maybe_value.map(|x| x.value.into_boxed_slice());
+    // Read a batch of keys with the cache locked only once.
+    pub fn get_many(&self, keys: &[Key]) -> Result<Vec<Option<Box<[u8]>>>> {
+        let mut write_guard = self.cache.write().unwrap();
+        keys.iter()
+            .map(|key| get_with_cache(&self.db_key, &mut write_guard, key))
+            .collect()
+    }
 
-        Ok(ans)
+    pub fn reader(&self) -> LvmtReader {
+        LvmtReader {
+            db_key: self.db_key.clone(),
+            cache: self.cache.clone(),
+        }
+    }
+
+    // Read the value of `key` after the commit of `epoch`.
+    pub fn get_at(&self, key: &Key, epoch: u64) -> Result<Option<Box<[u8]>>> {
+        let history_epochs = self
+            .history_epochs
+            .ok_or_else(|| Error::new(ErrorKind::Unsupported, "The history is not maintained"))?;
+        let current_epoch = self.current_epoch()?;
+        if epoch >= current_epoch || epoch + history_epochs < current_epoch {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Epoch {} is out of history", epoch),
+            ));
+        }
+
+        let history = Self::load_history(&*self.kvdb, key)?;
+        if history.is_empty() {
+            // The history of a key is dropped when it is not written in the kept epochs.
+            return self.get(key);
+        }
+        let value = history
+            .iter()
+            .rev()
+            .find(|(value_epoch, _)| *value_epoch <= epoch)
+            .map(|(_, value)| value.clone().into_boxed_slice());
+        Ok(value.filter(|x| !x.is_empty()))
+    }
+
+    // The values of a key in the kept epochs, sorted by epoch. It is empty if the key is not
+    // written in the kept epochs.
+    fn load_history(kvdb: &dyn KeyValueDB, key: &Key) -> Result<History> {
+        Ok(match kvdb.get(COL_HISTORY, key.as_ref())? {
+            Some(raw) => History::from_bytes_local(&raw)?,
+            None => History::new(),
+        })
     }
 
     pub fn set(&mut self, key: &Key, value: Box<[u8]>) {
@@ -151,6 +295,12 @@ impl LvmtDB {
         self.uncommitted_key_values.push((key.clone(), value))
     }
 
+    // The slot allocated to a key is never released, so a deletion is written as a tombstone: an
+    // empty value with a new version. `get` treats the tombstone as an absent key.
+    pub fn delete(&mut self, key: &Key) {
+        self.set(key, Box::new([]))
+    }
+
     pub fn current_epoch(&self) -> Result<u64> {
         let epoch = self
             .db_merkle
@@ -159,23 +309,104 @@ impl LvmtDB {
         Ok(epoch)
     }
 
+    // Write the histories of the keys written in `epoch`, and prune the histories of the keys
+    // written in the epoch leaving the history.
+    fn prune_histories(
+        &self,
+        epoch: u64,
+        history_epochs: u64,
+        mut histories: HashMap<Key, History>,
+    ) -> Result<()> {
+        let mut ops = Vec::new();
+        if !histories.is_empty() {
+            let written_keys: VecDeque<Vec<u8>> =
+                histories.keys().map(|key| key.0.clone()).collect();
+            ops.push(DBOp::Insert {
+                col: COL_HISTORY_INDEX,
+                key: epoch.to_bytes_local().into(),
+                value: written_keys.to_bytes_local(),
+            });
+        }
+
+        let oldest_epoch = (epoch + 1).saturating_sub(history_epochs);
+        let index_key = oldest_epoch.to_bytes_local();
+        if let Some(raw) = self.kvdb.get(COL_HISTORY_INDEX, &index_key)? {
+            for key in VecDeque::<Vec<u8>>::from_bytes_local(&raw)? {
+                let key = Key(key);
+                if !histories.contains_key(&key) {
+                    let history = Self::load_history(&*self.kvdb, &key)?;
+                    histories.insert(key, history);
+                }
+            }
+            ops.push(DBOp::Delete {
+                col: COL_HISTORY_INDEX,
+                key: index_key.into(),
+            });
+        }
+
+        for history in histories.values_mut() {
+            // Only the value at the oldest kept epoch is needed among the older values.
+            while history.len() > 1 && history[1].0 <= oldest_epoch {
+                history.pop_front();
+            }
+            // The value is unchanged in all the kept epochs, so the current value serves them.
+            if history.len() == 1 && history[0].0 <= oldest_epoch {
+                history.clear();
+            }
+        }
+        ops.extend(histories.into_iter().map(|(key, history)| {
+            if history.is_empty() {
+                DBOp::Delete {
+                    col: COL_HISTORY,
+                    key: key.as_ref().into(),
+                }
+            } else {
+                DBOp::Insert {
+                    col: COL_HISTORY,
+                    key: key.as_ref().into(),
+                    value: history.to_bytes_local(),
+                }
+            }
+        }));
+        if !ops.is_empty() {
+            self.kvdb.write_buffered(DBTransaction { ops });
+        }
+        Ok(())
+    }
+
     pub fn commit(&mut self, _epoch: u64) -> Result<(G1Projective, H256)> {
         let epoch = self.current_epoch()?;
 
         let kv_num = self.uncommitted_key_values.len();
         let mut hashes = Vec::with_capacity(kv_num);
         let mut write_ops = Vec::with_capacity(kv_num);
+        let mut histories: HashMap<Key, History> = HashMap::new();
+        let mut slot_key_ops = Vec::new();
 
         for (position, (key, value)) in self.uncommitted_key_values.drain(..).enumerate() {
-            let version: Option<VerInfo> = match self.cache.read().unwrap().get(&key).as_ref() {
-                Some(&(value, _)) => value.clone().map(|x| x.version),
+            let old_value: Option<Value> = match self.cache.read().unwrap().get(&key).as_ref() {
+                Some(&(value, _)) => value.clone(),
                 None => match self.db_key.get(key.as_ref())? {
                     None => None,
-                    Some(value) => Some(Value::from_bytes_local(&value)?.version),
+                    Some(value) => Some(Value::from_bytes_local(&value)?),
                 },
             };
+            let version: Option<VerInfo> = old_value.as_ref().map(|x| x.version);
 
+            let allocated = version.is_none();
             let version = self.version_tree.inc_key_ver(&key, version);
+            if allocated && self.keep_slot_keys && self.version_tree.in_proof_shard(&key) {
+                slot_key_ops.push(DBOp::Insert {
+                    col: COL_SLOT_KEY,
+                    key: slot_db_key(
+                        &key.tree_at_level(version.level),
+                        key.index_at_level(version.level),
+                        version.slot_index as usize,
+                    )
+                    .into(),
+                    value: key.0.clone(),
+                });
+            }
 
             let value = Value {
                 value: value.to_vec(),
@@ -194,6 +425,23 @@ impl LvmtDB {
                 value: value.to_bytes_local(),
             });
 
+            if self.history_epochs.is_some() {
+                let history = match histories.entry(key.clone()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(Self::load_history(&*self.kvdb, &key)?),
+                };
+                if let Some(old_value) = old_value.filter(|_| history.is_empty()) {
+                    // The dropped history starts again from the value before this epoch.
+                    history.push_back((old_value.position.epoch, old_value.value));
+                }
+                match history.back_mut() {
+                    Some((last_epoch, last_value)) if *last_epoch == epoch => {
+                        *last_value = value.value.clone()
+                    }
+                    _ => history.push_back((epoch, value.value.clone())),
+                }
+            }
+
             let key_ver_value_hash = keccak(
                 &KeyValue {
                     key: key.0,
@@ -206,6 +454,13 @@ impl LvmtDB {
             hashes.push(key_ver_value_hash);
         }
         self.db_key.write_buffered(DBTransaction { ops: write_ops });
+        if !slot_key_ops.is_empty() {
+            self.kvdb
+                .write_buffered(DBTransaction { ops: slot_key_ops });
+        }
+        if let Some(history_epochs) = self.history_epochs {
+            self.prune_histories(epoch, history_epochs, histories)?;
+        }
 
         // println!("commit position");
         let (amt_root, updates) = self.version_tree.commit(epoch, hashes.len() as u64);
@@ -239,13 +494,13 @@ impl LvmtDB {
         Ok((amt_root, merkle_root))
     }
 
-    // TODO: for non-existence proof.
     pub fn prove(&mut self, key: &Key) -> Result<Proof> {
-        let value = self
-            .db_key
-            .get(key.as_ref())?
-            .expect("We only support existent proof");
-        let value = Value::from_bytes_local(&value)?;
+        let value = match self.db_key.get(key.as_ref())? {
+            Some(value) => Value::from_bytes_local(&value)?,
+            None => {
+                return self.prove_non_existence(key);
+            }
+        };
         let ver_info = value.version;
 
         let maybe_value = Some(value.value);
@@ -272,6 +527,7 @@ impl LvmtDB {
             commitment,
             node_fr_int: node.as_fr_int(),
             node_version: node.key_versions[ver_info.slot_index as usize],
+            slot_proofs: Vec::new(),
         });
 
         for level in (0..ver_info.level).rev() {
@@ -294,12 +550,76 @@ impl LvmtDB {
                 commitment,
                 node_fr_int: node.as_fr_int(),
                 node_version: node.tree_version,
+                slot_proofs: Vec::new(),
             });
         }
 
         Ok((assoc_proof, level_proofs))
     }
 
+    // A key is absent if, in each level, the slots of its node are occupied by other keys until a
+    // node with vacant slots or without subtree. Otherwise, the key would be allocated there.
+    fn prove_non_existence(&mut self, key: &Key) -> Result<Proof> {
+        let mut level_proofs = VecDeque::new();
+        for level in 0..32 {
+            let tree_name = key.tree_at_level(level);
+            let index = key.index_at_level(level);
+            let (commitment, node, amt_proof) = self.prove_amt_node(tree_name.clone(), index);
+
+            let slot_proofs = (0..node.key_versions.len())
+                .map(|slot| self.prove_slot(&tree_name, index, slot))
+                .collect::<Result<Vec<_>>>()?;
+
+            let bottom = node.key_versions.len() < SLOT_NUMBER || node.tree_version == 0;
+            let (merkle_epoch, merkle_proof) = if bottom {
+                Default::default()
+            } else {
+                self.prove_merkle(node.tree_position)?
+            };
+
+            level_proofs.push_back(LevelProof {
+                merkle_epoch,
+                merkle_proof,
+                amt_proof,
+                commitment,
+                node_fr_int: node.as_fr_int(),
+                node_version: node.tree_version,
+                slot_proofs,
+            });
+
+            if bottom {
+                return Ok((AssociateProof::default(), level_proofs));
+            }
+        }
+        Err(Error::new(ErrorKind::Other, "Exceed maximum support level"))
+    }
+
+    fn prove_slot(&mut self, tree_name: &TreeName, index: usize, slot: usize) -> Result<SlotProof> {
+        if !self.keep_slot_keys {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "The slot keys are not maintained",
+            ));
+        }
+        let key = self
+            .kvdb
+            .get(COL_SLOT_KEY, &slot_db_key(tree_name, index, slot))?
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "The key in slot is not recorded"))?;
+        let value = self
+            .db_key
+            .get(&key)?
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "The key in slot does not exist"))?;
+        let value = Value::from_bytes_local(&value)?;
+        let (merkle_epoch, merkle_proof) = self.prove_merkle(value.position)?;
+        Ok(SlotProof {
+            key,
+            ver_info: value.version,
+            value: value.value,
+            merkle_epoch,
+            merkle_proof,
+        })
+    }
+
     pub fn verify<F: Fn(u64) -> H256>(
         key: &Key,
         proof: &Proof,
@@ -310,6 +630,10 @@ impl LvmtDB {
 
         let ver_info = assoc_proof.ver_info;
 
+        if level_proofs.is_empty() {
+            return Err("Empty proof".to_string());
+        }
+
         // Check the AMT proof
         for (level, level_proof) in level_proofs.iter().enumerate() {
             let amt_index = key.index_at_level(level as u8);
@@ -373,19 +697,19 @@ impl LvmtDB {
                 StaticMerkleTree::verify(&epoch_root(epoch), &key_ver_value_hash, merkle_proof);
 
             if !merkle_proof_verified {
-                println!(
-                    "Key {:?}, version {:?}, root{:?} hash {:?}",
-                    tree_name.clone(),
+                return Err(format!(
+                    "Incorrect Merkle proof at level {}: key {:?}, version {:?}, root {:?}, hash {:?}",
+                    level,
+                    tree_name,
                     version,
                     epoch_root(epoch),
                     key_ver_value_hash
-                );
-                return Err(format!("Incorrect Merkle proof at level {}", level));
+                ));
             }
         }
 
         // Check version consistency in the top level.
-        {
+        if assoc_proof.value.is_some() {
             let bottom_level_proof = &level_proofs[level_proofs.len() - 1];
             let version_verified = Node::versions_from_fr_int(
                 &bottom_level_proof.node_fr_int,
@@ -394,6 +718,8 @@ impl LvmtDB {
             if !version_verified {
                 return Err(format!("Inconsistent version value at level -1"));
             }
+        } else {
+            Self::verify_slots(key, level_proofs, &epoch_root)?;
         }
 
         // Check version consistency in the rest levels.
@@ -410,6 +736,70 @@ impl LvmtDB {
         Ok(())
     }
 
+    // Check that the slots in each level are occupied by other keys, and the search for vacant
+    // slot ends in the last level.
+    fn verify_slots<F: Fn(u64) -> H256>(
+        key: &Key,
+        level_proofs: &VecDeque<LevelProof>,
+        epoch_root: &F,
+    ) -> std::result::Result<(), String> {
+        let last_level = level_proofs.len() - 1;
+        for (level, level_proof) in level_proofs.iter().enumerate() {
+            let versions: Vec<u64> = (0..SLOT_NUMBER)
+                .map(|slot| Node::versions_from_fr_int(&level_proof.node_fr_int, slot + 1))
+                .collect();
+            let occupied = versions.iter().take_while(|&&version| version > 0).count();
+            if versions[occupied..].iter().any(|&version| version > 0)
+                || level_proof.slot_proofs.len() != occupied
+            {
+                return Err(format!("Inconsistent slots at level {}", level));
+            }
+
+            let tree_version = Node::versions_from_fr_int(&level_proof.node_fr_int, 0);
+            let bottom = occupied < SLOT_NUMBER || tree_version == 0;
+            if bottom != (level == last_level) {
+                return Err(format!("Unexpected end of slots at level {}", level));
+            }
+
+            let level = level as u8;
+            for (slot_index, slot_proof) in level_proof.slot_proofs.iter().enumerate() {
+                let slot_key = Key(slot_proof.key.clone());
+                if slot_key == *key
+                    || slot_key.tree_at_level(level) != key.tree_at_level(level)
+                    || slot_key.index_at_level(level) != key.index_at_level(level)
+                {
+                    return Err(format!("Incorrect slot key at level {}", level));
+                }
+
+                let slot_ver_info = slot_proof.ver_info;
+                if slot_ver_info.level != level
+                    || slot_ver_info.slot_index as usize != slot_index
+                    || slot_ver_info.version != versions[slot_index]
+                {
+                    return Err(format!("Inconsistent slot version at level {}", level));
+                }
+
+                let key_ver_value_hash = keccak(
+                    &KeyValue {
+                        key: slot_proof.key.clone(),
+                        version: slot_ver_info,
+                        value: slot_proof.value.clone(),
+                    }
+                    .to_bytes_consensus(),
+                );
+                let merkle_proof_verified = StaticMerkleTree::verify(
+                    &epoch_root(slot_proof.merkle_epoch),
+                    &key_ver_value_hash,
+                    &slot_proof.merkle_proof,
+                );
+                if !merkle_proof_verified {
+                    return Err(format!("Incorrect slot Merkle proof at level {}", level));
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn prove_amt_node(
         &mut self,
         name: TreeName,
@@ -432,12 +822,69 @@ impl LvmtDB {
         Ok((epoch_pos.epoch, merkle_proof))
     }
 
+    // The Merkle root of `epoch` kept in db, which is only available without `only_merkle_root`.
+    pub fn merkle_root(&self, epoch: u64) -> H256 {
+        *StaticMerkleTree::new(self.db_merkle.clone(), epoch).root()
+    }
+
     pub fn flush_root(&mut self) {
         self.version_tree.flush_all();
         self.kvdb.flush().unwrap();
     }
 }
 
+// The key of a slot in the slot key column.
+fn slot_db_key(tree_name: &TreeName, index: usize, slot: usize) -> Vec<u8> {
+    [
+        tree_name.to_bytes_local(),
+        (index as u32).to_be_bytes().to_vec(),
+        vec![slot as u8],
+    ]
+    .concat()
+}
+
+fn get_with_cache(db_key: &DBColumn, cache: &mut Cache, key: &Key) -> Result<Option<Box<[u8]>>> {
+    let entry = cache.entry(key.clone());
+
+    let maybe_value = match entry {
+        Entry::Occupied(entry) => entry.get().0.clone(),
+        Entry::Vacant(entry) => {
+            let value = db_key
+                .get(key.as_ref())?
+                .map(|x| Value::from_bytes_local(&x).unwrap());
+            entry.insert((value.clone(), false));
+            value
+        }
+    };
+
+    // let ans = self
+    //     .db_key
+    //     .get(key.as_ref())?
+    //     .map(|x| Value::from_bytes_local(&x).unwrap())
+    //     .map(|x| x.value.into_boxed_slice());
+    // An empty value is the tombstone of a deleted key.
+    let ans = maybe_value
+        .filter(|x| !x.value.is_empty())
+        .map(|x| x.value.into_boxed_slice());
+
+    Ok(ans)
+}
+
+// A read-only handle of the committed state. It shares the read cache with its `LvmtDB`, which
+// clears the cache after each commit.
+#[derive(Clone)]
+pub struct LvmtReader {
+    db_key: DBColumn,
+    cache: Arc<RwLock<Cache>>,
+}
+
+impl LvmtReader {
+    pub fn get(&self, key: &Key) -> Result<Option<Box<[u8]>>> {
+        let mut write_guard = self.cache.write().unwrap();
+        get_with_cache(&self.db_key, &mut write_guard, key)
+    }
+}
+
 pub fn cached_pp(dir: &str) -> Arc<AMTParams<Pairing>> {
     cached_pp_with_depth(dir, TypeDepths::USIZE)
 }
@@ -530,3 +977,152 @@ fn test_simple_db() {
         );
     }
 }
+
+#[test]
+fn test_non_existence_proof() {
+    use std::collections::HashMap;
+
+    let backend = crate::storage::test_kvdb(NUM_COLS);
+    let pp = Arc::new(AMTParams::<Pairing>::from_dir(
+        "./pp",
+        TypeDepths::USIZE,
+        true,
+    ));
+    let mut db = LvmtDB::new(backend, pp.clone(), false, Some((0, 0)));
+
+    let mut epoch_root_dict = HashMap::new();
+
+    for i in 0..=255 {
+        let epoch = db.current_epoch().unwrap();
+        db.set(&Key(vec![1, 2, i, 0]), vec![1, 2, i, 5].into());
+        let (_, epoch_root) = db.commit(epoch).unwrap();
+        epoch_root_dict.insert(epoch, epoch_root);
+    }
+
+    for i in 0..=40 {
+        let key = Key(vec![1, 2, i, 1]);
+        assert!(db.get(&key).unwrap().is_none());
+        let proof = db.prove(&key).unwrap();
+        assert!(proof.0.value.is_none());
+        LvmtDB::verify(&key, &proof, |epoch| epoch_root_dict[&epoch], &pp).unwrap();
+
+        // The proof can not show the absence of an existing key.
+        let existing_key = Key(vec![1, 2, i, 0]);
+        assert!(
+            LvmtDB::verify(&existing_key, &proof, |epoch| epoch_root_dict[&epoch], &pp).is_err()
+        );
+    }
+}
+
+#[test]
+fn test_historical_read() {
+    let backend = crate::storage::test_kvdb(NUM_COLS);
+    let pp = Arc::new(AMTParams::<Pairing>::from_dir(
+        "./pp",
+        TypeDepths::USIZE,
+        true,
+    ));
+    let mut db = LvmtDB::new(backend, pp, true, None);
+    db.set_history_epochs(Some(10));
+
+    for epoch in 0..10u8 {
+        for i in 0..=epoch {
+            db.set(&Key(vec![1, 2, i]), vec![epoch, i].into());
+        }
+        db.commit(epoch as u64).unwrap();
+    }
+
+    for epoch in 0..10u8 {
+        for i in 0..10u8 {
+            let expected = if i <= epoch {
+                Some(vec![epoch, i].into_boxed_slice())
+            } else {
+                None
+            };
+            assert_eq!(
+                db.get_at(&Key(vec![1, 2, i]), epoch as u64).unwrap(),
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn test_history_pruning() {
+    let backend = crate::storage::test_kvdb(NUM_COLS);
+    let pp = Arc::new(AMTParams::<Pairing>::from_dir(
+        "./pp",
+        TypeDepths::USIZE,
+        true,
+    ));
+    let mut db = LvmtDB::new(backend, pp, true, None);
+    db.set_history_epochs(Some(3));
+
+    let key = Key(vec![1, 2, 3]);
+    for epoch in 0..10u8 {
+        db.set(&key, vec![epoch].into());
+        db.commit(epoch as u64).unwrap();
+    }
+
+    assert_eq!(LvmtDB::load_history(&*db.kvdb, &key).unwrap().len(), 3);
+    for epoch in 7..10u8 {
+        assert_eq!(
+            db.get_at(&key, epoch as u64).unwrap(),
+            Some(vec![epoch].into_boxed_slice())
+        );
+    }
+    assert!(db.get_at(&key, 6).is_err());
+    assert!(db.get_at(&key, 10).is_err());
+
+    // The history of a key not written in the kept epochs is dropped.
+    for epoch in 10..13u64 {
+        db.commit(epoch).unwrap();
+    }
+    assert!(LvmtDB::load_history(&*db.kvdb, &key).unwrap().is_empty());
+    assert_eq!(
+        db.get_at(&key, 12).unwrap(),
+        Some(vec![9].into_boxed_slice())
+    );
+
+    db.set(&key, vec![13].into());
+    db.commit(13).unwrap();
+    assert_eq!(
+        db.get_at(&key, 12).unwrap(),
+        Some(vec![9].into_boxed_slice())
+    );
+    assert_eq!(
+        db.get_at(&key, 13).unwrap(),
+        Some(vec![13].into_boxed_slice())
+    );
+}
+
+#[test]
+fn test_delete() {
+    let backend = crate::storage::test_kvdb(NUM_COLS);
+    let pp = Arc::new(AMTParams::<Pairing>::from_dir(
+        "./pp",
+        TypeDepths::USIZE,
+        true,
+    ));
+    let mut db = LvmtDB::new(backend, pp, true, None);
+    db.set_history_epochs(Some(10));
+
+    db.set(&Key(vec![1, 2, 3]), vec![4, 5].into());
+    db.commit(0).unwrap();
+    db.delete(&Key(vec![1, 2, 3]));
+    db.commit(1).unwrap();
+
+    assert_eq!(db.get(&Key(vec![1, 2, 3])).unwrap(), None);
+    assert_eq!(
+        db.get_at(&Key(vec![1, 2, 3]), 0).unwrap(),
+        Some(vec![4, 5].into_boxed_slice())
+    );
+    assert_eq!(db.get_at(&Key(vec![1, 2, 3]), 1).unwrap(), None);
+
+    db.set(&Key(vec![1, 2, 3]), vec![6].into());
+    db.commit(2).unwrap();
+    assert_eq!(
+        db.get(&Key(vec![1, 2, 3])).unwrap(),
+        Some(vec![6].into_boxed_slice())
+    );
+}
@@ -6,7 +6,7 @@ mod tree;
 pub use self::{
     key::Key,
     name::TreeName,
-    node::{EpochPosition, Node, MAX_VERSION_NUMBER},
+    node::{EpochPosition, Node, MAX_VERSION_NUMBER, SLOT_NUMBER},
     tree::{AMTNodeIndex, VerInfo, VersionTree},
 };
 use crate::{
@@ -32,5 +32,3 @@ type Tree = AMTree<AMTConfig>;
 pub type Commitment = G1<<AMTConfig as AMTConfigTrait>::PE>;
 
 const DEPTHS: usize = <AMTConfig as AMTConfigTrait>::DEPTHS;
-
-//TODO: Store Key for non-existent proof
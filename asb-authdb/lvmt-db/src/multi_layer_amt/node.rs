@@ -10,6 +10,7 @@ pub(super) type FrInt = FrIntGeneric<Pairing>;
 
 pub const VERSION_BITS: usize = 40;
 pub const MAX_VERSION_NUMBER: u64 = (1 << VERSION_BITS) - 1;
+pub const SLOT_NUMBER: usize = 5;
 
 #[allow(dead_code)]
 fn const_assert() {
@@ -49,7 +50,7 @@ pub struct Node {
 impl AMTData<Fr> for Node {
     #[cfg(target_endian = "little")]
     fn as_fr_int(&self) -> FrInt {
-        assert!(self.key_versions.len() <= 5);
+        assert!(self.key_versions.len() <= SLOT_NUMBER);
         let mut result = [0u8; 32];
 
         let mut start: usize = 5;
@@ -19,7 +19,7 @@ use crate::multi_layer_amt::AMTConfig;
 use crate::serde::{MyFromBytes, MyToBytes};
 use crate::storage::DBColumn;
 
-use super::{Commitment, EpochPosition, Key, Tree, TreeName, MAX_VERSION_NUMBER};
+use super::{Commitment, EpochPosition, Key, Tree, TreeName, MAX_VERSION_NUMBER, SLOT_NUMBER};
 
 type NodeIndex = u32;
 type TreesLayer = HashMap<Vec<NodeIndex>, TreeWithInfo>;
@@ -125,16 +125,10 @@ impl VersionTree {
             Some(ver_info) => ver_info,
         };
 
-        let shard_node = self.shard_node.clone();
+        let in_proof_shard = self.in_proof_shard(key);
         let visit_amt = self.get_tree_mut(&key.tree_at_level(level));
         let node = key.index_at_level(level);
 
-        let in_proof_shard = if let Some(shard_node) = shard_node {
-            AMTNodeIndex::leaf(key.index_at_level(0)).needs_maintain(&shard_node)
-        } else {
-            false
-        };
-
         if !in_proof_shard {
             visit_amt.update(node, fr_int_pow_2((slot_index as u32 + 1) * 40));
         } else {
@@ -149,6 +143,14 @@ impl VersionTree {
         };
     }
 
+    pub(crate) fn in_proof_shard(&self, key: &Key) -> bool {
+        if let Some(shard_node) = &self.shard_node {
+            AMTNodeIndex::leaf(key.index_at_level(0)).needs_maintain(shard_node)
+        } else {
+            false
+        }
+    }
+
     pub fn allocate_vacant_slot(&mut self, key: &Key) -> VerInfo {
         for level in 0..32 {
             if level >= 3 {
@@ -157,7 +159,7 @@ impl VersionTree {
             let visit_amt = self.get_tree_mut(&key.tree_at_level(level));
             let node_index = key.index_at_level(level);
 
-            if visit_amt.get(node_index).key_versions.len() < 5 {
+            if visit_amt.get(node_index).key_versions.len() < SLOT_NUMBER {
                 let mut data = visit_amt.write_versions(node_index);
                 let slot_index = data.key_versions.len();
                 data.key_versions.push(0);
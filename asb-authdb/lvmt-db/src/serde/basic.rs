@@ -1,3 +1,4 @@
+use std::collections::VecDeque;
 use std::io::{Read, Result, Write};
 
 use super::{MyFromBytes, MyToBytes, SerdeType};
@@ -43,3 +44,58 @@ impl MyToBytes for Vec<u8> {
         Ok(())
     }
 }
+
+impl<T: MyFromBytes> MyFromBytes for Option<T> {
+    fn read<R: Read>(mut reader: R, ty: SerdeType) -> Result<Self> {
+        let flag: u8 = MyFromBytes::read(&mut reader, ty)?;
+        Ok(if flag == 0 {
+            None
+        } else {
+            Some(MyFromBytes::read(&mut reader, ty)?)
+        })
+    }
+}
+
+impl<T: MyToBytes> MyToBytes for Option<T> {
+    fn write<W: Write>(&self, mut writer: W, ty: SerdeType) -> Result<()> {
+        match self {
+            None => MyToBytes::write(&0u8, &mut writer, ty),
+            Some(value) => {
+                MyToBytes::write(&1u8, &mut writer, ty)?;
+                MyToBytes::write(value, &mut writer, ty)
+            }
+        }
+    }
+}
+
+impl<T: MyFromBytes> MyFromBytes for VecDeque<T> {
+    fn read<R: Read>(reader: R, ty: SerdeType) -> Result<Self> {
+        Ok(T::read_vec(reader, ty)?.into())
+    }
+}
+
+impl<T: MyToBytes> MyToBytes for VecDeque<T> {
+    fn write<W: Write>(&self, mut writer: W, ty: SerdeType) -> Result<()> {
+        MyToBytes::write(&self.len(), &mut writer, ty)?;
+        for item in self.iter() {
+            MyToBytes::write(item, &mut writer, ty)?;
+        }
+        Ok(())
+    }
+}
+
+impl<A: MyFromBytes, B: MyFromBytes> MyFromBytes for (A, B) {
+    fn read<R: Read>(mut reader: R, ty: SerdeType) -> Result<Self> {
+        Ok((
+            MyFromBytes::read(&mut reader, ty)?,
+            MyFromBytes::read(&mut reader, ty)?,
+        ))
+    }
+}
+
+impl<A: MyToBytes, B: MyToBytes> MyToBytes for (A, B) {
+    fn write<W: Write>(&self, mut writer: W, ty: SerdeType) -> Result<()> {
+        MyToBytes::write(&self.0, &mut writer, ty)?;
+        MyToBytes::write(&self.1, &mut writer, ty)
+    }
+}
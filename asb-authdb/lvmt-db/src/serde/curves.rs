@@ -1,7 +1,7 @@
 use std::io::{Read, Result, Write};
 
 use crate::crypto::export::{
-    AffineCurve, FromBytes, G1Affine, G1Projective, ProjectiveCurve, ToBytes,
+    instances::FrInt, AffineCurve, FromBytes, G1Affine, G1Projective, ProjectiveCurve, ToBytes,
 };
 
 use super::{MyFromBytes, MyToBytes, SerdeType};
@@ -43,3 +43,17 @@ impl MyToBytes for G1Affine {
         ToBytes::write(self, writer)
     }
 }
+
+impl MyFromBytes for FrInt {
+    #[inline]
+    fn read<R: Read>(reader: R, _ty: SerdeType) -> Result<Self> {
+        FromBytes::read(reader)
+    }
+}
+
+impl MyToBytes for FrInt {
+    #[inline]
+    fn write<W: Write>(&self, writer: W, _ty: SerdeType) -> Result<()> {
+        ToBytes::write(self, writer)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::amt::{AMTConfigTrait, AMTData, AMTree, NodeIndex};
+use crate::amt::{node::AMTNode, AMTConfigTrait, AMTData, AMTree, NodeIndex};
 use crate::crypto::export::{
     instances::{Fr, FrInt, G1Aff, G1},
     Pairing, ProjectiveCurve, Zero,
@@ -59,10 +59,17 @@ impl<const N: usize> AMTConfigTrait for AMTConfig<N> {
     type Height = Height<N>;
 }
 
+#[derive(Default, Clone, MyFromBytes, MyToBytes)]
+pub struct AmtProof {
+    value: Option<Vec<u8>>,
+    amt_proof: Vec<AMTNode<G1>>,
+}
+
 #[derive(Clone)]
 pub struct AmtDB<const N: usize> {
     root: G1,
     amt: Arc<RwLock<AMTree<AMTConfig<N>>>>,
+    pp: Arc<AMTParams<Pairing>>,
     pub db: Arc<dyn KeyValueDB>,
 }
 
@@ -80,12 +87,13 @@ impl<const N: usize> AmtDB<N> {
 
         let shard_root = shard_node.map(|(depth, index)| NodeIndex::<Height<N>>::new(depth, index));
 
-        let mut amt = AMTree::<AMTConfig<N>>::new(TreeName, db_col, pp, shard_root);
+        let mut amt = AMTree::<AMTConfig<N>>::new(TreeName, db_col, pp.clone(), shard_root);
         amt.set_commitment(&root);
 
         Self {
             root,
             amt: Arc::new(RwLock::new(amt)),
+            pp,
             db,
         }
     }
@@ -119,6 +127,53 @@ impl<const N: usize> AmtDB<N> {
         *amt.write_versions(Self::index(key)) = new_node;
     }
 
+    // A zero hash marks an empty slot.
+    pub fn delete(&mut self, key: &[u8]) {
+        let mut amt = self.amt.write().unwrap();
+        *amt.write_versions(Self::index(key)) = Node::default();
+    }
+
+    // The proof covers the whole slot of `key`, so it also works for absent keys.
+    pub fn prove(&self, key: &[u8]) -> Option<AmtProof> {
+        let mut amt = self.amt.write().unwrap();
+        let index = Self::index(key);
+        let amt_proof = amt.prove(index)?;
+        let node = amt.get(index);
+        let value = if node.hash == H256::zero() {
+            None
+        } else {
+            Some(node.data.clone())
+        };
+        Some(AmtProof { value, amt_proof })
+    }
+
+    pub fn verify(
+        &self,
+        key: &[u8],
+        proof: AmtProof,
+        commitment: &G1,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let node = match &proof.value {
+            None => Node::default(),
+            Some(value) => Node {
+                hash: keccak(value.as_slice()),
+                data: value.clone(),
+            },
+        };
+        let verified = AMTree::<AMTConfig<N>>::verify(
+            Self::index(key),
+            node.as_fr(),
+            commitment,
+            proof.amt_proof,
+            &self.pp,
+        );
+        if verified {
+            Ok(proof.value)
+        } else {
+            Err("Incorrect AMT proof".to_string())
+        }
+    }
+
     pub fn commit(&mut self) -> G1Aff {
         self.root = self.amt.write().unwrap().flush();
         self.db.write_buffered(DBTransaction {
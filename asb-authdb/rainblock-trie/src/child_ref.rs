@@ -94,21 +94,32 @@ impl ChildRef {
     }
 
     #[inline]
-    pub fn exile<const N: usize>(&self, depth: usize, exile_nodes: &mut Vec<NodePtrWeak>) {
+    pub fn exile(&self, depth: usize, top_layer_depth: usize, exile_nodes: &mut Vec<NodePtrWeak>) {
         if let Self::Owned(node) = self {
-            TrieNodeExt::exile::<N>(node, depth, exile_nodes);
+            TrieNodeExt::exile(node, depth, top_layer_depth, exile_nodes);
         }
     }
 
     #[inline]
-    pub fn commit<const N: usize>(
+    pub fn commit(
         me: &RefCell<Self>,
         depth: usize,
+        top_layer_depth: usize,
         put_ops: &mut Vec<(H256, Vec<u8>)>,
         top_layer: bool,
     ) {
         if let Self::Owned(node) = &*me.borrow() {
-            node.as_ref().commit::<N>(depth, put_ops, top_layer)
+            node.as_ref()
+                .commit(depth, top_layer_depth, put_ops, top_layer)
+        }
+    }
+
+    #[inline]
+    pub fn memory_usage(me: &RefCell<Self>) -> (usize, usize) {
+        if let Self::Owned(node) = &*me.borrow() {
+            node.as_ref().memory_usage()
+        } else {
+            (0, 0)
         }
     }
 
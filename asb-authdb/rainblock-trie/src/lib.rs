@@ -11,13 +11,13 @@ mod thread_non_safe;
 #[cfg(not(feature = "thread-safe"))]
 pub use thread_non_safe::{Node, NodePtr, NodePtrWeak};
 #[cfg(not(feature = "thread-safe"))]
-unsafe impl<const N: usize> Send for MerklePatriciaTree<N> {} // As internal Rc does not expose to outsize, it is safe to declare it as Send.
+unsafe impl Send for MerklePatriciaTree {} // As internal Rc does not expose to outsize, it is safe to declare it as Send.
 #[cfg(feature = "thread-safe")]
 mod thread_safe;
 #[cfg(feature = "thread-safe")]
 pub use thread_safe::{Node, NodePtr, NodePtrWeak};
 
-pub use rain_mpt::MerklePatriciaTree;
+pub use rain_mpt::{CommittedReader, MerklePatriciaTree};
 
 fn common_prefix_iter<'a, T: Eq>(a: &'a [T], b: &'a [T]) -> impl Iterator<Item = &'a T> {
     a.iter()
@@ -1,7 +1,13 @@
-use std::{io, sync::Arc};
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    sync::{Arc, RwLock},
+};
 
 use ethereum_types::H256;
+use hash_db::Hasher;
 use kvdb::{DBOp, DBTransaction, KeyValueDB};
+use rlp::{Decodable, Rlp};
 
 use crate::{
     add_prefix,
@@ -10,17 +16,51 @@ use crate::{
     nibble::{bytes_to_nibble_list, Nibble},
     trie_node::{NextResult, TrieNode},
     trie_node_ext::TrieNodeExt,
-    NodePtrWeak,
+    NodePtrWeak, RlpHasher,
 };
 
 use crate::NodePtr;
 
-pub struct MerklePatriciaTree<const TOP_LAYER_DEPTH: usize> {
+pub struct MerklePatriciaTree {
     pub db: Arc<dyn KeyValueDB>,
+    // The nodes shallower than this depth are kept in memory after the commits.
+    top_layer_depth: usize,
     root: Option<NodePtr>,
     del_ops: Vec<H256>,
     loaded_node: Vec<NodePtrWeak>,
     exile_nodes: Vec<NodePtrWeak>,
+    // The total numbers of loaded nodes and exile nodes handled by the commits.
+    loaded_count: u64,
+    exile_count: u64,
+    // The roots of the latest committed epochs, the front is the latest one.
+    history: VecDeque<Option<NodePtr>>,
+    history_depth: usize,
+    // The stale nodes of the latest commits with the history, the front is the latest one. They
+    // are deleted once the states referring to them leave the history.
+    stale_nodes: VecDeque<(u64, Vec<H256>)>,
+    // The commit making each queued node stale. A node written again is live and removed.
+    stale_since: HashMap<H256, u64>,
+    commit_count: u64,
+    // The root of the latest commit, only kept for the reads of the committed state. A commit
+    // holds the write lock until it replaces the root.
+    committed: Option<Arc<RwLock<Option<NodePtr>>>>,
+}
+
+/// A handle reading the state of the latest commit, which does not borrow the trie. The readers
+/// only wait for the commits, not for the reads and writes of the trie.
+pub struct CommittedReader {
+    db: Arc<dyn KeyValueDB>,
+    root: Arc<RwLock<Option<NodePtr>>>,
+}
+
+impl CommittedReader {
+    pub fn get(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>, String> {
+        if key.is_empty() {
+            return Err("Empty key is not supported".to_string());
+        }
+        let root = self.root.read().unwrap();
+        MerklePatriciaTree::lookup_in_memory(&self.db, &root, key)
+    }
 }
 
 struct SearchResult {
@@ -50,12 +90,15 @@ impl SearchResult {
 const ROOT_KEY: [u8; 1] = [0x80];
 pub const EMPTY_ROOT: H256 = H256::zero();
 
-impl<const N: usize> MerklePatriciaTree<N> {
-    pub fn new(db: Arc<dyn KeyValueDB>) -> MerklePatriciaTree<N> {
+impl MerklePatriciaTree {
+    /// Open the trie in `db`, keeping the nodes shallower than `top_layer_depth` in memory. The
+    /// depth must be at least 1, so the root stays in memory.
+    pub fn new(db: Arc<dyn KeyValueDB>, top_layer_depth: usize) -> MerklePatriciaTree {
+        assert!(top_layer_depth > 0, "The top layer depth must be positive");
         let root = if let Some(digest) = db.get(0, &ROOT_KEY).expect("Cannot load db") {
             let digest = H256::from_slice(&digest);
             let node = TrieNodeExt::load(&db, digest).seal();
-            node.as_ref().load_children::<N>(&db, 0);
+            node.as_ref().load_children(&db, 0, top_layer_depth);
             Some(node)
         } else {
             None
@@ -64,12 +107,59 @@ impl<const N: usize> MerklePatriciaTree<N> {
         MerklePatriciaTree {
             root,
             db,
+            top_layer_depth,
             del_ops: vec![],
             loaded_node: vec![],
             exile_nodes: vec![],
+            loaded_count: 0,
+            exile_count: 0,
+            history: VecDeque::new(),
+            history_depth: 0,
+            stale_nodes: VecDeque::new(),
+            stale_since: HashMap::new(),
+            commit_count: 0,
+            committed: None,
+        }
+    }
+
+    /// Keep the root of the latest commit for `get_committed`. The nodes shared with the
+    /// committed state are copied on write, so the committed state stays intact until the next
+    /// commit.
+    pub fn keep_committed_root(&mut self) {
+        self.committed = Some(Arc::new(RwLock::new(self.root.clone())));
+    }
+
+    /// A reader of the state of the latest commit. It requires `keep_committed_root`.
+    pub fn committed_reader(&self) -> CommittedReader {
+        CommittedReader {
+            db: self.db.clone(),
+            root: self
+                .committed
+                .clone()
+                .expect("The committed root is not kept"),
         }
     }
 
+    /// Keep the states of the latest `depth` epochs for historical read. The stale nodes are only
+    /// deleted from db once the states referring to them leave the history.
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history_depth = depth;
+        self.history.truncate(depth);
+    }
+
+    /// The total numbers of loaded nodes truncated and exile nodes written by the commits.
+    pub fn node_counts(&self) -> (u64, u64) {
+        (self.loaded_count, self.exile_count)
+    }
+
+    /// The number of trie nodes held in memory and their approximate size in bytes. Between the
+    /// commits, these are the top layers cached above `top_layer_depth`.
+    pub fn cached_memory(&self) -> (usize, usize) {
+        self.root
+            .as_ref()
+            .map_or((0, 0), |root| root.as_ref().memory_usage())
+    }
+
     pub fn root(&self) -> Option<H256> {
         self.root.as_ref().map(|x| x.as_ref().hash())
     }
@@ -127,7 +217,14 @@ impl<const N: usize> MerklePatriciaTree<N> {
     }
 
     pub fn commit(&mut self) -> io::Result<H256> {
+        // The commit truncates the nodes shared with the committed state and deletes the stale
+        // nodes, so its readers wait until the root is replaced.
+        let committed = self.committed.clone();
+        let mut committed_root = committed.as_ref().map(|root| root.write().unwrap());
+
         let mut put_ops = Vec::new();
+        self.loaded_count += self.loaded_node.len() as u64;
+        self.exile_count += self.exile_nodes.len() as u64;
 
         for node in self.exile_nodes.drain(..) {
             if let Some(node) = node.upgrade() {
@@ -138,7 +235,8 @@ impl<const N: usize> MerklePatriciaTree<N> {
         }
 
         if let Some(root) = &self.root {
-            root.as_ref().commit::<N>(0, &mut put_ops, false);
+            root.as_ref()
+                .commit(0, self.top_layer_depth, &mut put_ops, false);
         } else {
             self.db.write_buffered(DBTransaction {
                 ops: vec![DBOp::Delete {
@@ -154,7 +252,12 @@ impl<const N: usize> MerklePatriciaTree<N> {
             }
         }
 
-        for del_op in self.del_ops.drain(..) {
+        let del_ops = if self.history_depth > 0 {
+            self.expire_stale_nodes(&put_ops)
+        } else {
+            std::mem::take(&mut self.del_ops)
+        };
+        for del_op in del_ops {
             self.db.write_buffered(DBTransaction {
                 ops: vec![DBOp::Delete {
                     col: 0,
@@ -188,13 +291,168 @@ impl<const N: usize> MerklePatriciaTree<N> {
 
         self.db.flush()?;
 
+        if self.history_depth > 0 {
+            self.history.push_front(self.root.clone());
+            self.history.truncate(self.history_depth);
+        }
+        if let Some(committed_root) = &mut committed_root {
+            **committed_root = self.root.clone();
+        }
+
         Ok(hash)
     }
 
+    // Queue the nodes made stale by this commit, and return the queued nodes which are no longer
+    // referred to by the states kept in the history. A node made stale by the commit `c` belongs to
+    // the state of the commit `c - 1`, which leaves the history at the commit `c + depth - 1`.
+    fn expire_stale_nodes(&mut self, put_ops: &[(H256, Vec<u8>)]) -> Vec<H256> {
+        self.commit_count += 1;
+        let commit = self.commit_count;
+        for hash in self.del_ops.iter() {
+            self.stale_since.insert(*hash, commit);
+        }
+        for (hash, _) in put_ops.iter() {
+            self.stale_since.remove(hash);
+        }
+        self.stale_nodes
+            .push_front((commit, std::mem::take(&mut self.del_ops)));
+
+        let mut expired = Vec::new();
+        while self.stale_nodes.len() >= self.history_depth {
+            let (commit, hashes) = self.stale_nodes.pop_back().unwrap();
+            for hash in hashes {
+                if self.stale_since.get(&hash) == Some(&commit) {
+                    self.stale_since.remove(&hash);
+                    expired.push(hash);
+                }
+            }
+        }
+        expired
+    }
+
+    /// Read `key` in the state committed `epochs_ago` epochs before the latest commit.
+    pub fn get_at(&self, epochs_ago: usize, key: Vec<u8>) -> Result<Option<Vec<u8>>, String> {
+        if key.is_empty() {
+            return Err("Empty key is not supported".to_string());
+        }
+        let root = self
+            .history
+            .get(epochs_ago)
+            .ok_or_else(|| format!("Epoch {} ago is out of history", epochs_ago))?;
+        Self::lookup_in_memory(&self.db, root, key)
+    }
+
+    /// Read `key` in the state of the latest commit, ignoring the writes after it. It requires
+    /// `keep_committed_root`.
+    pub fn get_committed(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>, String> {
+        self.committed_reader().get(key)
+    }
+
+    // Look up `key` from a root kept in memory. The hashed children are loaded from db.
+    fn lookup_in_memory(
+        db: &Arc<dyn KeyValueDB>,
+        root: &Option<NodePtr>,
+        key: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let root = match root {
+            Some(root) => (**root.as_ref()).clone(),
+            None => return Ok(None),
+        };
+        let load = |digest: &H256| -> Result<TrieNode, String> {
+            Ok((*TrieNodeExt::load(db, *digest)).clone())
+        };
+        Self::lookup(root, key, load)
+    }
+
+    // The proof is the RLP encoding of the nodes on the search path. The small nodes are embedded
+    // in their parents, so only the root and the hashed nodes are included.
+    pub fn prove(&mut self, key: Vec<u8>) -> Result<Vec<Vec<u8>>, String> {
+        if key.is_empty() {
+            return Err("Empty key is not supported".to_string());
+        }
+        if self.root.is_none() {
+            return Ok(vec![]);
+        }
+        let result = self.search(key);
+        Ok(result
+            .stack
+            .iter()
+            .enumerate()
+            .filter(|(depth, (node, _))| *depth == 0 || !node.as_ref().is_small_node())
+            .map(|(_, (node, _))| node.as_ref().get_rlp_encode())
+            .collect())
+    }
+
+    pub fn verify(root: H256, key: Vec<u8>, proof: &[Vec<u8>]) -> Result<Option<Vec<u8>>, String> {
+        if key.is_empty() {
+            return Err("Empty key is not supported".to_string());
+        }
+        if root == EMPTY_ROOT {
+            return Ok(None);
+        }
+
+        let nodes: HashMap<H256, &Vec<u8>> = proof
+            .iter()
+            .map(|rlp| (RlpHasher::hash(rlp), rlp))
+            .collect();
+        let load = |digest: &H256| -> Result<TrieNode, String> {
+            let rlp = nodes
+                .get(digest)
+                .ok_or_else(|| format!("Missing node {:x?}", digest))?;
+            TrieNode::decode(&Rlp::new(rlp)).map_err(|e| e.to_string())
+        };
+
+        Self::lookup(load(&root)?, key, load)
+    }
+
+    // Look up `key` from `root` without modifying the trie. The hashed children are loaded by `load`.
+    fn lookup<F: Fn(&H256) -> Result<TrieNode, String>>(
+        root: TrieNode,
+        key: Vec<u8>,
+        load: F,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let mut node = root;
+        let mut remainder = bytes_to_nibble_list(key);
+        let value = loop {
+            let child = match &node {
+                TrieNode::Branch { children, value } => {
+                    if remainder.is_empty() {
+                        break value.clone();
+                    }
+                    let child = children[remainder[0]].borrow().clone();
+                    remainder = remainder[1..].to_vec();
+                    child
+                }
+                TrieNode::Extension { key, child } => {
+                    if !remainder.starts_with(key) {
+                        break vec![];
+                    }
+                    remainder = remainder[key.len()..].to_vec();
+                    child.borrow().clone()
+                }
+                TrieNode::Leaf { key, value } => {
+                    if *key == remainder {
+                        break value.clone();
+                    } else {
+                        break vec![];
+                    }
+                }
+            };
+            node = match child {
+                ChildRef::Null => break vec![],
+                ChildRef::Ref(digest) => load(&digest)?,
+                ChildRef::Owned(child) => (**child.as_ref()).clone(),
+            };
+        };
+
+        Ok(if value.is_empty() { None } else { Some(value) })
+    }
+
     pub fn flush_all(&mut self) -> io::Result<()> {
         let mut put_ops = Vec::new();
         if let Some(root) = &self.root {
-            root.as_ref().commit::<N>(0, &mut put_ops, true);
+            root.as_ref()
+                .commit(0, self.top_layer_depth, &mut put_ops, true);
         }
 
         for (key, value) in put_ops.drain(..) {
@@ -229,6 +487,14 @@ impl<const N: usize> MerklePatriciaTree<N> {
         }
     }
 
+    pub fn delete(&mut self, key: Vec<u8>) -> Result<(), String> {
+        if key.is_empty() {
+            return Err("Empty key is not supported".to_string());
+        }
+        self.del(key);
+        Ok(())
+    }
+
     fn del(&mut self, key: Vec<u8>) {
         if self.root.is_none() {
             return;
@@ -274,12 +540,13 @@ impl<const N: usize> MerklePatriciaTree<N> {
         let mut remainder = bytes_to_nibble_list(key);
         let mut depth = 1;
         let matched = loop {
-            match TrieNode::next::<N>(
+            match TrieNode::next(
                 &node,
                 &mut remainder,
                 &self.db,
                 &mut self.loaded_node,
                 depth,
+                self.top_layer_depth,
             ) {
                 NextResult::Matched => {
                     break true;
@@ -304,7 +571,7 @@ impl<const N: usize> MerklePatriciaTree<N> {
     }
 }
 
-impl<const N: usize> MerklePatriciaTree<N> {
+impl MerklePatriciaTree {
     fn insert(
         &mut self,
         last_node: &mut TrieNode,
@@ -367,7 +634,7 @@ impl<const N: usize> MerklePatriciaTree<N> {
                 let first_key = *key.first().unwrap();
                 *key = key[1..].to_vec();
                 std::mem::drop(trie_node);
-                prev_branch_node.exile::<N>(depth + 2, &mut self.exile_nodes);
+                prev_branch_node.exile(depth + 2, self.top_layer_depth, &mut self.exile_nodes);
                 *new_branch_children[first_key].get_mut() = std::mem::take(prev_branch_node);
             }
             TrieNode::Branch { .. } => {
@@ -401,7 +668,7 @@ impl<const N: usize> MerklePatriciaTree<N> {
             let child_node_borrow = child_node.as_ref();
             if let TrieNode::Branch { .. } = &**child_node_borrow {
                 std::mem::drop(child_node_borrow);
-                child.exile::<N>(depth + 2, &mut self.exile_nodes);
+                child.exile(depth + 2, self.top_layer_depth, &mut self.exile_nodes);
                 *child = ChildRef::Owned(
                     TrieNode::new_extention(ext_key_rest, std::mem::take(child)).seal(),
                 );
@@ -412,7 +679,7 @@ impl<const N: usize> MerklePatriciaTree<N> {
                 add_prefix(key, &ext_key_rest);
             }
         }
-        child.exile::<N>(depth + 2, &mut self.exile_nodes);
+        child.exile(depth + 2, self.top_layer_depth, &mut self.exile_nodes);
         *new_branch_children[ext_key_next].get_mut() = std::mem::take(child);
 
         if let Some(remainder_next) = remainder_next {
@@ -462,7 +729,7 @@ impl<const N: usize> MerklePatriciaTree<N> {
     }
 }
 
-impl<const N: usize> MerklePatriciaTree<N> {
+impl MerklePatriciaTree {
     fn remove(&mut self, stack: &mut Vec<(NodePtr, Option<Nibble>)>) {
         // #[cfg(test)] {
         //     println!("Show stack");
@@ -18,7 +18,7 @@ fn new_db() -> Arc<dyn KeyValueDB> {
 fn test_put_random() {
     let mut rng = StdRng::seed_from_u64(123);
 
-    let mut trie = MerklePatriciaTree::<3>::new(new_db());
+    let mut trie = MerklePatriciaTree::new(new_db(), 3);
     let mut tasks: Vec<(Bytes, Bytes)> = (0..=255u8).map(|x| (vec![x], vec![x])).collect();
     tasks.shuffle(&mut rng);
 
@@ -46,7 +46,7 @@ fn test_put_random() {
         );
     }
 
-    let mut another_trie = MerklePatriciaTree::<3>::new(new_db());
+    let mut another_trie = MerklePatriciaTree::new(new_db(), 3);
 
     for i in 0..=255 {
         another_trie.put(vec![i], vec![i]);
@@ -65,7 +65,7 @@ fn comprehensive_test_with_similar_prefix() {
     let make_value = |x: usize| -> Vec<u8> { vec![x as u8] };
     const SAMPLES: usize = 256;
 
-    let mut trie = MerklePatriciaTree::<1000>::new(new_db());
+    let mut trie = MerklePatriciaTree::new(new_db(), 1000);
     let mut tasks: Vec<usize> = (0..SAMPLES).collect();
     tasks.shuffle(&mut rng);
 
@@ -96,7 +96,7 @@ fn comprehensive_test_with_similar_prefix() {
 
     // Check put consistensy
     let db2 = new_db();
-    let mut trie2 = MerklePatriciaTree::<3>::new(db2.clone());
+    let mut trie2 = MerklePatriciaTree::new(db2.clone(), 3);
     for i in 0..SAMPLES {
         trie2.put(make_key(i), make_value(i));
     }
@@ -123,7 +123,7 @@ fn comprehensive_test_with_similar_prefix() {
     assert_eq!(cached_nodes + dumped_nodes, new_dumped_nodes);
 
     // Check reload trie from db
-    let mut trie2 = MerklePatriciaTree::<3>::new(db2.clone());
+    let mut trie2 = MerklePatriciaTree::new(db2.clone(), 3);
     trie2.commit().unwrap();
     assert!(trie2.loaded_nodes_count() <= 5);
 
@@ -148,3 +148,171 @@ fn comprehensive_test_with_similar_prefix() {
     // Check no leak on db
     assert!(db2.iter_from_prefix(0, &vec![]).next().is_none());
 }
+
+#[test]
+fn test_prove_and_verify() {
+    let mut rng = StdRng::seed_from_u64(125);
+    let make_key = |x: usize| -> Vec<u8> { vec![x as u8, (x * 7) as u8] };
+    let make_value = |x: usize| -> Vec<u8> { vec![x as u8; x % 40 + 1] };
+    const SAMPLES: usize = 200;
+
+    let mut trie = MerklePatriciaTree::new(new_db(), 3);
+    let mut tasks: Vec<usize> = (0..SAMPLES).collect();
+    tasks.shuffle(&mut rng);
+    for i in tasks.drain(..) {
+        trie.put(make_key(i), make_value(i));
+    }
+    let root = trie.commit().unwrap();
+
+    // Check existent proof
+    for i in 0..SAMPLES {
+        let proof = trie.prove(make_key(i)).unwrap();
+        assert_eq!(
+            MerklePatriciaTree::verify(root, make_key(i), &proof),
+            Ok(Some(make_value(i))),
+            "Fail on position {}",
+            i
+        );
+    }
+
+    // Check non-existent proof
+    for i in SAMPLES..256 {
+        let proof = trie.prove(make_key(i)).unwrap();
+        assert_eq!(
+            MerklePatriciaTree::verify(root, make_key(i), &proof),
+            Ok(None),
+            "Fail on position {}",
+            i
+        );
+    }
+
+    // Check proof against a wrong root
+    let proof = trie.prove(make_key(0)).unwrap();
+    trie.put(make_key(0), vec![0xff]);
+    let new_root = trie.commit().unwrap();
+    assert!(MerklePatriciaTree::verify(new_root, make_key(0), &proof).is_err());
+}
+
+#[test]
+fn test_historical_read() {
+    let make_key = |x: usize| -> Vec<u8> { vec![x as u8, (x * 7) as u8] };
+    const SAMPLES: usize = 200;
+    const EPOCHS: usize = 8;
+
+    let mut trie = MerklePatriciaTree::new(new_db(), 3);
+    trie.set_history_depth(EPOCHS);
+    for epoch in 0..EPOCHS {
+        for i in 0..SAMPLES {
+            if i % EPOCHS >= epoch {
+                trie.put(make_key(i), vec![epoch as u8, i as u8]);
+            }
+        }
+        trie.commit().unwrap();
+    }
+
+    for epochs_ago in 0..EPOCHS {
+        let epoch = EPOCHS - 1 - epochs_ago;
+        for i in 0..SAMPLES {
+            let last_write = usize::min(i % EPOCHS, epoch);
+            assert_eq!(
+                trie.get_at(epochs_ago, make_key(i)),
+                Ok(Some(vec![last_write as u8, i as u8])),
+                "Fail on position {} at epoch {}",
+                i,
+                epoch
+            );
+        }
+    }
+    assert!(trie.get_at(EPOCHS, make_key(0)).is_err());
+}
+
+#[test]
+fn test_history_pruning() {
+    let make_key = |x: usize| -> Vec<u8> { vec![x as u8, (x * 7) as u8] };
+    const SAMPLES: usize = 200;
+    const EPOCHS: usize = 4;
+
+    let (db, history_db) = (new_db(), new_db());
+    let mut trie = MerklePatriciaTree::new(db.clone(), 1);
+    let mut history_trie = MerklePatriciaTree::new(history_db.clone(), 1);
+    history_trie.set_history_depth(EPOCHS);
+    for epoch in 0..EPOCHS * 3 {
+        for i in (0..SAMPLES).filter(|i| i % 3 == epoch % 3) {
+            trie.put(make_key(i), vec![epoch as u8, i as u8]);
+            history_trie.put(make_key(i), vec![epoch as u8, i as u8]);
+        }
+        assert_eq!(trie.commit().unwrap(), history_trie.commit().unwrap());
+    }
+    let count = |db: &Arc<dyn KeyValueDB>| db.iter_from_prefix(0, &[]).count();
+    assert!(count(&history_db) > count(&db));
+
+    // The stale nodes are deleted once the states before the last writes leave the history.
+    for _ in 1..EPOCHS {
+        trie.commit().unwrap();
+        history_trie.commit().unwrap();
+    }
+    assert_eq!(count(&history_db), count(&db));
+}
+
+#[test]
+fn test_cached_memory() {
+    let mut rng = StdRng::seed_from_u64(126);
+    let tasks: Vec<(Bytes, Bytes)> = (0..256)
+        .map(|_| (rng.gen::<[u8; 32]>().to_vec(), vec![1u8; 32]))
+        .collect();
+
+    let mut shallow_trie = MerklePatriciaTree::new(new_db(), 1);
+    let mut deep_trie = MerklePatriciaTree::new(new_db(), 1000);
+    for (key, value) in tasks.iter() {
+        shallow_trie.put(key.clone(), value.clone());
+        deep_trie.put(key.clone(), value.clone());
+    }
+    assert_eq!(shallow_trie.commit().unwrap(), deep_trie.commit().unwrap());
+
+    // Only the root stays in memory with one cached level.
+    let (shallow_nodes, shallow_bytes) = shallow_trie.cached_memory();
+    let (deep_nodes, deep_bytes) = deep_trie.cached_memory();
+    assert_eq!(shallow_nodes, 1);
+    assert!(deep_nodes > tasks.len());
+    assert!(deep_bytes > shallow_bytes);
+}
+
+#[test]
+fn test_committed_read() {
+    let make_key = |x: usize| -> Vec<u8> { vec![x as u8, (x * 7) as u8] };
+    const SAMPLES: usize = 200;
+
+    let mut trie = MerklePatriciaTree::new(new_db(), 3);
+    trie.keep_committed_root();
+    let reader = trie.committed_reader();
+    for i in 0..SAMPLES {
+        trie.put(make_key(i), vec![0, i as u8]);
+    }
+    assert_eq!(trie.get_committed(make_key(0)), Ok(None));
+    assert!(trie.get_committed(vec![]).is_err());
+    trie.commit().unwrap();
+
+    // The writes after the commit are only visible after the next commit.
+    for i in 0..SAMPLES / 2 {
+        trie.put(make_key(i), vec![1, i as u8]);
+    }
+    for i in 0..SAMPLES {
+        assert_eq!(
+            trie.get_committed(make_key(i)),
+            Ok(Some(vec![0, i as u8])),
+            "Fail on position {} before commit",
+            i
+        );
+    }
+    trie.commit().unwrap();
+    for i in 0..SAMPLES {
+        let epoch = if i < SAMPLES / 2 { 1 } else { 0 };
+        assert_eq!(
+            trie.get_committed(make_key(i)),
+            Ok(Some(vec![epoch, i as u8])),
+            "Fail on position {} after commit",
+            i
+        );
+        assert_eq!(reader.get(make_key(i)), Ok(Some(vec![epoch, i as u8])));
+    }
+}
@@ -7,15 +7,17 @@ use crate::{trie_node::TrieNode, trie_node_ext::TrieNodeExt};
 
 pub struct Node(pub Mutex<TrieNodeExt>);
 
+// The nodes shared with the committed state may be visited by its readers at the same time, so
+// the shared accesses wait for the lock.
 impl Clone for Node {
     fn clone(&self) -> Self {
-        Self(Mutex::new(self.0.try_lock().unwrap().clone()))
+        Self(Mutex::new(self.0.lock().unwrap().clone()))
     }
 }
 
 impl Node {
     pub fn as_ref(&self) -> impl Deref<Target = TrieNodeExt> + '_ {
-        self.0.try_lock().unwrap()
+        self.0.lock().unwrap()
     }
 
     pub fn as_mut(&mut self) -> impl DerefMut<Target = TrieNodeExt> + '_ {
@@ -44,7 +46,7 @@ impl NodePtr {
     }
 
     pub fn as_ref(&self) -> impl Deref<Target = TrieNodeExt> + '_ {
-        self.0.deref().0.try_lock().unwrap()
+        self.0.deref().0.lock().unwrap()
     }
 }
 
@@ -128,12 +128,13 @@ impl TrieNode {
         }
     }
 
-    pub fn next<const N: usize>(
+    pub fn next(
         me: &NodePtr,
         nibbles: &mut Vec<Nibble>,
         db: &Arc<dyn KeyValueDB>,
         truncate_ops: &mut Vec<NodePtrWeak>,
         depth: usize,
+        top_layer_depth: usize,
     ) -> NextResult {
         match &**me.as_ref() {
             Branch { children, .. } => {
@@ -144,7 +145,7 @@ impl TrieNode {
                 let branch_key = *nibbles.first().unwrap();
                 let branch = &children[branch_key];
                 if let Some((next, is_loaded)) = ChildRef::owned_or_load(branch, db) {
-                    if is_loaded && depth >= N {
+                    if is_loaded && depth >= top_layer_depth {
                         truncate_ops.push(NodePtr::downgrade(&me));
                     }
                     *nibbles = nibbles[1..].to_vec();
@@ -157,7 +158,7 @@ impl TrieNode {
                 if nibbles[..].starts_with(&key) {
                     *nibbles = nibbles[key.len()..].to_vec();
                     let (node, is_loaded) = ChildRef::owned_or_load(child, db).unwrap();
-                    if is_loaded && depth >= N {
+                    if is_loaded && depth >= top_layer_depth {
                         truncate_ops.push(NodePtr::downgrade(&me));
                     }
                     NextResult::Next((node, Nibble::zero()))
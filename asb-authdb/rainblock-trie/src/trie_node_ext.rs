@@ -9,6 +9,7 @@ use hash_db::Hasher;
 use kvdb::KeyValueDB;
 use rlp::Encodable;
 
+use crate::nibble::Nibble;
 use crate::trie_node::TrieNode;
 use crate::Node;
 use crate::RlpHasher;
@@ -135,8 +136,8 @@ impl TrieNodeExt {
         self.rlp_encode.borrow().as_ref().unwrap().clone()
     }
 
-    pub fn load_children<const N: usize>(&self, db: &Arc<dyn KeyValueDB>, depth: usize) {
-        if depth >= N - 1 {
+    pub fn load_children(&self, db: &Arc<dyn KeyValueDB>, depth: usize, top_layer_depth: usize) {
+        if depth >= top_layer_depth - 1 {
             return;
         }
         assert!(self.commited.get() != Working);
@@ -144,27 +145,28 @@ impl TrieNodeExt {
             TrieNode::Branch { children, .. } => {
                 for child in children.iter() {
                     if let Some((node, _)) = ChildRef::owned_or_load(child, db) {
-                        node.as_ref().load_children::<N>(db, depth + 1);
+                        node.as_ref().load_children(db, depth + 1, top_layer_depth);
                     }
                 }
             }
             TrieNode::Extension { child, .. } => {
                 if let Some((node, _)) = ChildRef::owned_or_load(child, db) {
-                    node.as_ref().load_children::<N>(db, depth + 1);
+                    node.as_ref().load_children(db, depth + 1, top_layer_depth);
                 }
             }
             _ => {}
         }
     }
 
-    pub fn commit<const N: usize>(
+    pub fn commit(
         &self,
         depth: usize,
+        top_layer_depth: usize,
         put_ops: &mut Vec<(H256, Vec<u8>)>,
         top_layer: bool,
     ) {
         let bottom_layer = !top_layer;
-        if depth >= N && top_layer {
+        if depth >= top_layer_depth && top_layer {
             return;
         }
 
@@ -177,11 +179,11 @@ impl TrieNodeExt {
         match &self.node {
             TrieNode::Branch { children, .. } => {
                 for child in children.iter() {
-                    ChildRef::commit::<N>(child, depth + 1, put_ops, top_layer);
+                    ChildRef::commit(child, depth + 1, top_layer_depth, put_ops, top_layer);
                 }
             }
             TrieNode::Extension { child, .. } => {
-                ChildRef::commit::<N>(child, depth + 1, put_ops, top_layer);
+                ChildRef::commit(child, depth + 1, top_layer_depth, put_ops, top_layer);
             }
             _ => {}
         }
@@ -189,7 +191,7 @@ impl TrieNodeExt {
         let rlp_encode = self.get_rlp_encode();
         if rlp_encode.len() >= 32 || depth == 0 {
             let hash = self.hash();
-            if depth >= N || top_layer {
+            if depth >= top_layer_depth || top_layer {
                 put_ops.push((hash, rlp_encode));
             }
             self.commited.set(Committed);
@@ -197,7 +199,7 @@ impl TrieNodeExt {
             assert_eq!(self.commited.get(), Light);
         }
 
-        if depth == N - 1 && bottom_layer {
+        if depth == top_layer_depth - 1 && bottom_layer {
             self.truncate();
         }
     }
@@ -216,28 +218,62 @@ impl TrieNodeExt {
         }
     }
 
-    pub fn exile<const N: usize>(me: &NodePtr, depth: usize, exile_nodes: &mut Vec<NodePtrWeak>) {
-        if depth > N {
+    pub fn exile(
+        me: &NodePtr,
+        depth: usize,
+        top_layer_depth: usize,
+        exile_nodes: &mut Vec<NodePtrWeak>,
+    ) {
+        if depth > top_layer_depth {
             return;
         }
 
-        if depth == N {
+        if depth == top_layer_depth {
             exile_nodes.push(NodePtr::downgrade(me));
             return;
         }
         match &**me.as_ref() {
             TrieNode::Branch { children, .. } => {
                 for child in children.iter() {
-                    child.borrow().exile::<N>(depth + 1, exile_nodes);
+                    child
+                        .borrow()
+                        .exile(depth + 1, top_layer_depth, exile_nodes);
                 }
             }
             TrieNode::Extension { child, .. } => {
-                child.borrow().exile::<N>(depth + 1, exile_nodes);
+                child
+                    .borrow()
+                    .exile(depth + 1, top_layer_depth, exile_nodes);
             }
             _ => {}
         }
     }
 
+    /// The number of nodes held in memory from this node, and their approximate size in bytes,
+    /// including the cached RLP encodings.
+    pub fn memory_usage(&self) -> (usize, usize) {
+        let rlp_len = self.rlp_encode.borrow().as_ref().map_or(0, Vec::len);
+        let payload_len = match &self.node {
+            TrieNode::Leaf { key, value } => {
+                key.len() * std::mem::size_of::<Nibble>() + value.len()
+            }
+            TrieNode::Branch { value, .. } => value.len(),
+            TrieNode::Extension { key, .. } => key.len() * std::mem::size_of::<Nibble>(),
+        };
+        let (mut nodes, mut bytes) = (1, std::mem::size_of::<Self>() + rlp_len + payload_len);
+        let children: Vec<&RefCell<ChildRef>> = match &self.node {
+            TrieNode::Branch { children, .. } => children.iter().collect(),
+            TrieNode::Extension { child, .. } => vec![child],
+            TrieNode::Leaf { .. } => vec![],
+        };
+        for child in children {
+            let (child_nodes, child_bytes) = ChildRef::memory_usage(child);
+            nodes += child_nodes;
+            bytes += child_bytes;
+        }
+        (nodes, bytes)
+    }
+
     #[cfg(test)]
     pub fn loaded_nodes_count(&self) -> usize {
         1 + match &self.node {
@@ -2,14 +2,20 @@ use std::sync::Arc;
 
 use kvdb::KeyValueDB;
 
-use lvmt_db::{lvmt_db::cached_pp_with_depth, single_amt::AmtDB};
+use lvmt_db::crypto::export::{AffineCurve, G1Affine};
+use lvmt_db::serde::{MyFromBytes, MyToBytes};
+use lvmt_db::{
+    lvmt_db::cached_pp_with_depth,
+    single_amt::{AmtDB, AmtProof},
+    storage::access::PUT_COUNT,
+};
 
 use asb_options::Options;
-use authdb::AuthDB;
+use asb_profile::{CounterTrait, Metric};
+use authdb::{AuthDB, Commitment, Proof, ProofError, StateRoot};
 
 pub struct Amt<const N: usize> {
     amt: AmtDB<N>,
-    print_root_period: Option<usize>,
 }
 
 pub fn new<const N: usize>(backend: Arc<dyn KeyValueDB>, opts: &Options) -> Amt<N> {
@@ -18,11 +24,6 @@ pub fn new<const N: usize>(backend: Arc<dyn KeyValueDB>, opts: &Options) -> Amt<
     let shard_info = opts.shards.map(|size| (size.trailing_zeros() as usize, 0));
     Amt {
         amt: AmtDB::new(backend, pp, shard_info),
-        print_root_period: if opts.print_root {
-            Some(opts.report_epoch)
-        } else {
-            None
-        },
     }
 }
 
@@ -37,14 +38,37 @@ impl<const N: usize> AuthDB for Amt<N> {
         self.amt.set(&key, value)
     }
 
-    fn commit(&mut self, index: usize) {
+    fn delete(&mut self, key: Vec<u8>) {
+        self.amt.delete(&key)
+    }
+
+    fn commit(&mut self, _index: usize) -> StateRoot {
         // println!("commit");
         let root = self.amt.commit();
-        if let Some(period) = self.print_root_period {
-            if index % period == 0 {
-                println!("Commitment {:?}", root);
-            }
-        }
+        StateRoot::Amt(Commitment(root.to_bytes_consensus()))
+    }
+
+    fn prove(&mut self, key: Vec<u8>) -> Result<Proof, ProofError> {
+        let proof = self.amt.prove(&key).ok_or(ProofError::Unsupported)?;
+        Ok(Proof(proof.to_bytes_consensus()))
+    }
+
+    // `root` is the affine commitment of the AMT.
+    fn verify(
+        &self,
+        key: Vec<u8>,
+        proof: &Proof,
+        root: &[u8],
+    ) -> Result<Option<Box<[u8]>>, ProofError> {
+        let invalid = |e: std::io::Error| ProofError::Invalid(e.to_string());
+        let proof = AmtProof::from_bytes_consensus(&proof.0).map_err(invalid)?;
+        let commitment = G1Affine::from_bytes_consensus(root)
+            .map_err(invalid)?
+            .into_projective();
+        self.amt
+            .verify(&key, proof, &commitment)
+            .map(|value| value.map(Into::into))
+            .map_err(ProofError::Invalid)
     }
 
     fn backend(&self) -> Option<&dyn KeyValueDB> {
@@ -75,3 +99,39 @@ impl Default for AMTCounter {
         }
     }
 }
+
+// The node writes counted in `PUT_COUNT`, indexed by the put mode of the AMT flush and the LVMT
+// Merkle tree.
+const PUT_MODE_NAMES: [&str; 4] = [
+    "data_node_writes",
+    "inner_node_writes",
+    "subtree_root_writes",
+    "merkle_node_writes",
+];
+
+/// Report the node writes of the first `modes` put modes since `last`, and update `last`.
+pub(crate) fn put_count_metrics(last: &mut [u64; 4], modes: usize) -> Vec<Metric> {
+    let put_count = *PUT_COUNT.lock().unwrap();
+    let metrics = PUT_MODE_NAMES[..modes]
+        .iter()
+        .zip(put_count.iter().zip(last.iter()))
+        .map(|(name, (now, before))| Metric::counter(*name, now - before))
+        .collect();
+    *last = put_count;
+    metrics
+}
+
+#[derive(Default)]
+pub struct AmtCounter {
+    put_count: [u64; 4],
+}
+
+impl CounterTrait for AmtCounter {
+    fn reset(&mut self) {
+        self.put_count = *PUT_COUNT.lock().unwrap();
+    }
+
+    fn report(&mut self) -> Vec<Metric> {
+        put_count_metrics(&mut self.put_count, 3)
+    }
+}
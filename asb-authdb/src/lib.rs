@@ -6,9 +6,12 @@ mod mpt;
 mod rain_mpt;
 mod raw;
 
+use amt::AmtCounter;
 use lvmt::LvmtCounter;
 use mpt::MptCounter;
+use rain_mpt::RainCounter;
 
+use asb_backend::BatchGet;
 use asb_options::{AuthAlgo, Options};
 use asb_profile::CounterTrait;
 use asb_profile::{Counter, Reporter};
@@ -28,33 +31,59 @@ fn open_lmpts(dir: &str) -> Box<dyn AuthDB> {
     }
 }
 
-pub fn new<'a>(backend: Arc<dyn KeyValueDB>, opts: &'a Options) -> (Box<dyn AuthDB>, Reporter<'a>) {
-    let (db, counter): (Box<dyn AuthDB>, Box<dyn CounterTrait>) = match opts.algorithm {
-        AuthAlgo::RAW => (Box::new(raw::new(backend)), Box::new(Counter::default())),
+pub fn new<'a>(
+    backend: Arc<dyn KeyValueDB>,
+    batch_get: Option<Arc<dyn BatchGet>>,
+    opts: &'a Options,
+) -> (Box<dyn AuthDB>, Reporter<'a>) {
+    let (db, counter) = open(&opts.algorithm, backend, batch_get, opts);
+
+    let mut reporter = Reporter::new(opts);
+    reporter.set_counter(counter);
+
+    return (db, reporter);
+}
+
+/// Open `algorithm` on a fresh in-memory backend, as the reference of a differential check.
+pub fn new_reference(algorithm: &AuthAlgo, opts: &Options) -> Box<dyn AuthDB> {
+    if *algorithm == AuthAlgo::LMPTS {
+        panic!("LMPTs can not be a reference, it does not work on the in-memory backend")
+    }
+    let backend = asb_backend::in_memory_backend(algorithm.num_cols());
+    open(algorithm, backend, None, opts).0
+}
+
+fn open(
+    algorithm: &AuthAlgo,
+    backend: Arc<dyn KeyValueDB>,
+    batch_get: Option<Arc<dyn BatchGet>>,
+    opts: &Options,
+) -> (Box<dyn AuthDB>, Box<dyn CounterTrait>) {
+    match *algorithm {
+        AuthAlgo::RAW => (
+            Box::new(raw::new(backend, batch_get, opts)),
+            Box::new(Counter::default()),
+        ),
         AuthAlgo::LVMT => (
             Box::new(lvmt::new(backend, opts)),
             Box::new(LvmtCounter::default()),
         ),
-        AuthAlgo::MPT => {
-            let mpt_db = mpt::new(backend, opts);
+        AuthAlgo::MPT(journal, journal_epoch) => {
+            let mpt_db = mpt::new(backend, journal, journal_epoch, opts);
             let counter = MptCounter::from_mpt_db(&mpt_db);
             (Box::new(mpt_db), Box::new(counter))
         }
         AuthAlgo::LMPTS => (open_lmpts(&opts.db_dir), Box::new(Counter::default())),
         AuthAlgo::AMT(x) => {
             let authdb = exaust_construct!(x, backend, opts, 20, 21, 22, 23, 24, 25, 26, 27, 28);
-            (authdb, Box::new(Counter::default()))
+            (authdb, Box::new(AmtCounter::default()))
         }
-        AuthAlgo::RAIN => (
-            Box::new(rain_mpt::new(backend)),
-            Box::new(Counter::default()),
-        ),
-    };
-
-    let mut reporter = Reporter::new(opts);
-    reporter.set_counter(counter);
-
-    return (db, reporter);
+        AuthAlgo::RAIN => {
+            let rain_mpt = rain_mpt::new(backend, opts);
+            let counter = RainCounter::from_rain_mpt(&rain_mpt, opts);
+            (Box::new(rain_mpt), Box::new(counter))
+        }
+    }
 }
 
 macro_rules! exaust_construct {
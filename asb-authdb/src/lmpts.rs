@@ -2,7 +2,7 @@ use asb_backend::cfx_storage::{
     state::StateTrait, state_manager::StateManagerTrait, StateIndex, StorageConfiguration,
     StorageManager, StorageState,
 };
-use authdb::AuthDB;
+use authdb::{AuthDB, StateRoot};
 use cfx_primitives::StorageKey;
 use kvdb::KeyValueDB;
 use primitive_types::H256;
@@ -32,7 +32,12 @@ impl AuthDB for Lmpts {
         self.state.set(key, value.into_boxed_slice()).unwrap()
     }
 
-    fn commit(&mut self, index: usize) {
+    fn delete(&mut self, key: Vec<u8>) {
+        let key = StorageKey::AccountKey(key.as_slice());
+        self.state.delete(key).unwrap()
+    }
+
+    fn commit(&mut self, index: usize) -> StateRoot {
         let mut epoch_id = H256::default();
         epoch_id.0[0..8].copy_from_slice(index.to_le_bytes().as_ref());
 
@@ -50,7 +55,8 @@ impl AuthDB for Lmpts {
             .manager
             .get_state_for_next_epoch(state_index)
             .expect("unwrap result")
-            .expect("unwrap option")
+            .expect("unwrap option");
+        StateRoot::Hash(state_root.aux_info.state_root_hash)
     }
 
     fn backend(&self) -> Option<&dyn KeyValueDB> {
@@ -1,31 +1,54 @@
 use asb_options::Options;
-use asb_profile::CounterTrait;
-use authdb::AuthDB;
+use asb_profile::{CounterTrait, Metric};
+use authdb::{AuthDB, AuthDBReader, Commitment, HistoryError, Proof, ProofError, StateRoot};
 use kvdb::KeyValueDB;
-use lvmt_db::crypto::export::ProjectiveCurve;
+use lvmt_db::crypto::export::{AffineCurve, G1Affine, Pairing, ProjectiveCurve};
+use lvmt_db::crypto::AMTParams;
+use lvmt_db::serde::{MyFromBytes, MyToBytes};
 use lvmt_db::{
-    lvmt_db::{cached_pp, LvmtDB, INC_KEY_COUNT, INC_KEY_LEVEL_SUM, INC_TREE_COUNT},
+    lvmt_db::{cached_pp, LvmtDB, LvmtReader, INC_KEY_COUNT, INC_KEY_LEVEL_SUM, INC_TREE_COUNT},
     multi_layer_amt::Key,
     storage::access::PUT_COUNT,
 };
+use primitive_types::H256;
+use std::collections::{HashMap, VecDeque};
+use std::io::ErrorKind;
 use std::sync::Arc;
 
+use crate::amt::put_count_metrics;
+
 pub struct Lvmt {
     amt: LvmtDB,
-    print_root_period: Option<usize>,
+    pp: Arc<AMTParams<Pairing>>,
+    // Merkle roots of the epochs committed in this run, as known by a light client.
+    epoch_roots: Option<HashMap<u64, H256>>,
+    // The number of the latest epochs kept for historical read.
+    history_depth: Option<usize>,
+    // The LVMT epochs of the latest commits in this run, the back is the latest one.
+    history_epochs: VecDeque<(usize, u64)>,
 }
 
 pub fn new(backend: Arc<dyn KeyValueDB>, opts: &Options) -> Lvmt {
     let pp = cached_pp("./pp");
     pp.warm_quotient();
     let shard_info = opts.shards.map(|size| (size.trailing_zeros() as usize, 0));
+    let mut amt = LvmtDB::new(
+        backend,
+        pp.clone(),
+        opts.lvmt_only_merkle_root(),
+        shard_info,
+    );
+    amt.set_history_epochs(opts.history_epochs.map(|epochs| epochs as u64));
     Lvmt {
-        amt: LvmtDB::new(backend, pp, true, shard_info),
-        print_root_period: if opts.print_root {
-            Some(opts.report_epoch)
+        amt,
+        pp,
+        epoch_roots: if opts.enable_proof {
+            Some(HashMap::new())
         } else {
             None
         },
+        history_depth: opts.history_epochs,
+        history_epochs: VecDeque::new(),
     }
 }
 
@@ -35,20 +58,114 @@ impl AuthDB for Lvmt {
         self.amt.get(&Key(key)).unwrap()
     }
 
+    fn get_many(&self, keys: &[Vec<u8>]) -> Vec<Option<Box<[u8]>>> {
+        let keys: Vec<Key> = keys.iter().cloned().map(Key).collect();
+        self.amt.get_many(&keys).unwrap()
+    }
+
     fn set(&mut self, key: Vec<u8>, value: Vec<u8>) {
         // println!("write");
         self.amt.set(&Key(key), value.into_boxed_slice())
     }
 
-    fn commit(&mut self, index: usize) {
+    fn delete(&mut self, key: Vec<u8>) {
+        self.amt.delete(&Key(key))
+    }
+
+    fn commit(&mut self, index: usize) -> StateRoot {
         // println!("commit");
+        let epoch = self.amt.current_epoch().unwrap();
         let (commit, root) = self.amt.commit(index as u64).unwrap();
-        if let Some(period) = self.print_root_period {
-            if index % period == 0 {
-                let aff_comm = commit.into_affine();
-                println!("Commitment {:?}, Merkle {:?}", aff_comm, root);
+        if let Some(epoch_roots) = &mut self.epoch_roots {
+            epoch_roots.insert(epoch, root);
+        }
+        if let Some(history_depth) = self.history_depth {
+            self.history_epochs.push_back((index, epoch));
+            if self.history_epochs.len() > history_depth {
+                self.history_epochs.pop_front();
+            }
+        }
+        StateRoot::Lvmt {
+            commitment: Commitment(commit.into_affine().to_bytes_consensus()),
+            merkle: root,
+        }
+    }
+
+    fn support_history(&self) -> bool {
+        self.history_depth.is_some()
+    }
+
+    fn get_at(&self, epoch: usize, key: Vec<u8>) -> Result<Option<Box<[u8]>>, HistoryError> {
+        if self.history_depth.is_none() {
+            return Err(HistoryError::Unsupported);
+        }
+        let (_, lvmt_epoch) = self
+            .history_epochs
+            .iter()
+            .find(|(index, _)| *index == epoch)
+            .ok_or(HistoryError::Unavailable(epoch))?;
+        self.amt
+            .get_at(&Key(key), *lvmt_epoch)
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => HistoryError::Unavailable(epoch),
+                _ => HistoryError::Backend(e.to_string()),
+            })
+    }
+
+    fn prove(&mut self, key: Vec<u8>) -> Result<Proof, ProofError> {
+        if self.epoch_roots.is_none() {
+            return Err(ProofError::Unsupported);
+        }
+        let proof = self
+            .amt
+            .prove(&Key(key))
+            .map_err(|e| ProofError::Invalid(e.to_string()))?;
+        Ok(Proof(proof.to_bytes_consensus()))
+    }
+
+    // `root` is the affine commitment of the root AMT.
+    fn verify(
+        &self,
+        key: Vec<u8>,
+        proof: &Proof,
+        root: &[u8],
+    ) -> Result<Option<Box<[u8]>>, ProofError> {
+        let epoch_roots = self.epoch_roots.as_ref().ok_or(ProofError::Unsupported)?;
+        let invalid = |e: std::io::Error| ProofError::Invalid(e.to_string());
+        let proof = lvmt_db::Proof::from_bytes_consensus(&proof.0).map_err(invalid)?;
+        let commitment = G1Affine::from_bytes_consensus(root)
+            .map_err(invalid)?
+            .into_projective();
+
+        let (assoc_proof, level_proofs) = &proof;
+        match level_proofs.front() {
+            Some(top_level) if *top_level.commitment() == commitment => {}
+            _ => {
+                return Err(ProofError::Invalid(
+                    "Inconsistent commitment at level 0".to_string(),
+                ))
             }
         }
+
+        let key = Key(key);
+        // The roots of the epochs before this run, e.g. of a loaded warmup, are read from db.
+        let epoch_root = |epoch| {
+            epoch_roots
+                .get(&epoch)
+                .cloned()
+                .unwrap_or_else(|| self.amt.merkle_root(epoch))
+        };
+        LvmtDB::verify(&key, &proof, epoch_root, &self.pp).map_err(ProofError::Invalid)?;
+        // A tombstone proves the key is deleted.
+        Ok(assoc_proof
+            .value()
+            .filter(|x| !x.is_empty())
+            .map(|x| x.clone().into_boxed_slice()))
+    }
+
+    // The reader sees the last committed state, as the writes are buffered until commit.
+    fn reader(&self) -> Option<Box<dyn AuthDBReader>> {
+        Some(Box::new(Reader(self.amt.reader())))
     }
 
     fn backend(&self) -> Option<&dyn KeyValueDB> {
@@ -60,7 +177,7 @@ impl AuthDB for Lvmt {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct LvmtCounter {
     put_count: [u64; 4],
     inc_key_count: u64,
@@ -68,20 +185,15 @@ pub struct LvmtCounter {
     inc_key_level_count: u64,
 }
 
-impl Default for LvmtCounter {
-    fn default() -> Self {
-        Self {
-            put_count: [0; 4],
-            inc_key_count: 0,
-            inc_tree_count: 0,
-            inc_key_level_count: 0,
-        }
+impl CounterTrait for LvmtCounter {
+    fn reset(&mut self) {
+        self.put_count = *PUT_COUNT.lock().unwrap();
+        self.inc_key_count = *INC_KEY_COUNT.lock().unwrap();
+        self.inc_tree_count = *INC_TREE_COUNT.lock().unwrap();
+        self.inc_key_level_count = *INC_KEY_LEVEL_SUM.lock().unwrap();
     }
-}
 
-impl CounterTrait for LvmtCounter {
-    fn report(&mut self) -> String {
-        let put_count = *PUT_COUNT.lock().unwrap();
+    fn report(&mut self) -> Vec<Metric> {
         let inc_key_count = *INC_KEY_COUNT.lock().unwrap();
         let inc_tree_count = *INC_TREE_COUNT.lock().unwrap();
         let inc_key_level_count = *INC_KEY_LEVEL_SUM.lock().unwrap();
@@ -89,25 +201,19 @@ impl CounterTrait for LvmtCounter {
         let key_diff = inc_key_count - self.inc_key_count;
         let tree_diff = inc_tree_count - self.inc_tree_count;
         let level_diff = inc_key_level_count - self.inc_key_level_count;
-        let avg_level = (level_diff as f64) / (key_diff as f64);
-
-        let answer = format!(
-            "avg levels: {:.3}, access writes {:?}, data writes {} {}",
-            avg_level,
-            self.put_count
-                .iter()
-                .zip(put_count.iter())
-                .map(|(x, y)| y - x)
-                .collect::<Vec<u64>>(),
-            key_diff * 2,
-            tree_diff * 2,
-        );
 
-        self.put_count = *PUT_COUNT.lock().unwrap();
-        self.inc_key_count = *INC_KEY_COUNT.lock().unwrap();
-        self.inc_tree_count = *INC_TREE_COUNT.lock().unwrap();
-        self.inc_key_level_count = *INC_KEY_LEVEL_SUM.lock().unwrap();
+        let mut metrics = vec![Metric::ratio(
+            "avg_key_levels",
+            (level_diff as f64) / (key_diff as f64),
+        )];
+        metrics.extend(put_count_metrics(&mut self.put_count, 4));
+        metrics.push(Metric::counter("key_data_writes", key_diff * 2));
+        metrics.push(Metric::counter("tree_data_writes", tree_diff * 2));
+
+        self.inc_key_count = inc_key_count;
+        self.inc_tree_count = inc_tree_count;
+        self.inc_key_level_count = inc_key_level_count;
 
-        answer
+        metrics
     }
 }
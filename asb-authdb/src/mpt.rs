@@ -1,59 +1,159 @@
 use std::cell::RefCell;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::Arc;
 
-use hash_db::Hasher;
+use hash_db::{HashDB, Hasher};
 use kvdb::{DBKey, DBOp, DBTransaction, KeyValueDB};
+use memory_db::MemoryDB;
 use patricia_trie_ethereum::RlpNodeCodec;
 use primitive_types::H256;
-use trie_db::{NodeCodec, Trie, TrieMut};
+use trie_db::node::Node;
+use trie_db::{NibbleSlice, NodeCodec, Recorder, Trie, TrieMut};
 
 use parity_journaldb::{Algorithm, DBHasher, JournalDB};
-use parity_scale_codec::KeyedVec;
+use parity_scale_codec::{Decode, Encode, KeyedVec};
 
-use asb_options::Options;
-use asb_profile::CounterTrait;
-use authdb::AuthDB;
+use asb_options::{Journal, Options};
+use asb_profile::{CounterTrait, Metric};
+use authdb::{AuthDB, HistoryError, Proof, ProofError, StateRoot};
+
+const NULL_RLP: [u8; 1] = [0x80];
 
 pub type TrieDBMut<'db> = trie_db::TrieDBMut<'db, DBHasher, RlpNodeCodec<DBHasher>>;
 pub type TrieDB<'db> = trie_db::TrieDB<'db, DBHasher, RlpNodeCodec<DBHasher>>;
 
+// There is no concurrent reader, as the journal database can not be shared among threads.
 pub struct MptDB {
     backing: Arc<dyn KeyValueDB>,
     db: Arc<RefCell<Box<dyn JournalDB>>>,
     root: H256,
-    epoch: usize,
-    print_root_period: Option<usize>,
+    // The number of recent epochs kept before pruning.
     journal_epoch: usize,
+    // The committed epochs not marked canonical yet, at most `journal_epoch` of them.
+    recent_epochs: VecDeque<usize>,
+    // The number of the latest epochs kept for historical read.
+    history_depth: Option<usize>,
+    // The roots of the latest epochs committed in this run, the back is the latest one.
+    history_roots: VecDeque<(usize, H256)>,
 }
 
 fn epoch_hash(epoch: usize) -> H256 {
     DBHasher::hash(&epoch.to_le_bytes())
 }
 
-pub(crate) fn new(backend: Arc<dyn KeyValueDB>, opts: &Options) -> MptDB {
-    let db = parity_journaldb::new(backend.clone(), Algorithm::OverlayRecent, 0);
-    let db = Arc::new(RefCell::new(db));
-    let print_root_period = if opts.print_root {
-        Some(opts.report_epoch)
-    } else {
-        None
+pub(crate) fn new(
+    backend: Arc<dyn KeyValueDB>,
+    journal: Journal,
+    journal_epoch: usize,
+    opts: &Options,
+) -> MptDB {
+    // Historical read requires all the trie nodes to be kept, which the options have checked.
+    debug_assert!(opts.history_epochs.is_none() || journal == Journal::Archive);
+    let algorithm = match journal {
+        Journal::Archive => Algorithm::Archive,
+        Journal::EarlyMerge => Algorithm::EarlyMerge,
+        Journal::OverlayRecent => Algorithm::OverlayRecent,
+        Journal::RefCounted => Algorithm::RefCounted,
     };
+    let db = parity_journaldb::new(backend.clone(), algorithm, 0);
+    let db = Arc::new(RefCell::new(db));
     let root = if let Some(value) = backend.get([0u8; 256].to_vec()) {
         H256::from_slice(&value)
     } else {
         RlpNodeCodec::<DBHasher>::hashed_null_node()
     };
 
-    let journal_epoch = 0;
-
     MptDB {
         db,
         backing: backend,
         root,
-        epoch: 0,
-        print_root_period,
         journal_epoch,
+        recent_epochs: VecDeque::new(),
+        history_depth: opts.history_epochs,
+        history_roots: VecDeque::new(),
+    }
+}
+
+fn nibble_at(key: &[u8], depth: usize) -> u8 {
+    if depth % 2 == 0 {
+        key[depth / 2] >> 4
+    } else {
+        key[depth / 2] & 0x0f
+    }
+}
+
+// Whether `key` continues with the nibbles of `partial` after its first `depth` nibbles.
+fn follows(key: &[u8], depth: usize, partial: &NibbleSlice) -> bool {
+    key.len() * 2 >= depth + partial.len()
+        && (0..partial.len()).all(|i| nibble_at(key, depth + i) == partial.at(i))
+}
+
+// Looks up the sorted `keys` under the encoded `node`. All the keys share the first `depth`
+// nibbles, which lead to this node.
+fn get_sorted(
+    hash_db: &dyn HashDB<DBHasher, trie_db::DBValue>,
+    node: &[u8],
+    depth: usize,
+    keys: &[(usize, &[u8])],
+    answers: &mut [Option<Box<[u8]>>],
+) {
+    match RlpNodeCodec::<DBHasher>::decode(node).unwrap() {
+        Node::Empty => {}
+        Node::Leaf(partial, value) => {
+            for (idx, key) in keys {
+                if key.len() * 2 == depth + partial.len() && follows(key, depth, &partial) {
+                    answers[*idx] = Some(value.to_vec().into_boxed_slice());
+                }
+            }
+        }
+        Node::Extension(partial, child) => {
+            let keys: Vec<(usize, &[u8])> = keys
+                .iter()
+                .filter(|(_, key)| follows(key, depth, &partial))
+                .cloned()
+                .collect();
+            if !keys.is_empty() {
+                get_child(hash_db, child, depth + partial.len(), &keys, answers);
+            }
+        }
+        Node::Branch(children, value) => {
+            // The keys ending at this node are sorted before the longer keys.
+            let mut start = 0;
+            while start < keys.len() && keys[start].1.len() * 2 == depth {
+                answers[keys[start].0] = value.map(|x| x.to_vec().into_boxed_slice());
+                start += 1;
+            }
+            // The remaining keys are grouped by the next nibble.
+            while start < keys.len() {
+                let nibble = nibble_at(keys[start].1, depth);
+                let end = start
+                    + keys[start..]
+                        .iter()
+                        .take_while(|(_, key)| nibble_at(key, depth) == nibble)
+                        .count();
+                if let Some(child) = children[nibble as usize] {
+                    get_child(hash_db, child, depth + 1, &keys[start..end], answers);
+                }
+                start = end;
+            }
+        }
+    }
+}
+
+// A child is either the hash of a node or the node itself if it is short.
+fn get_child(
+    hash_db: &dyn HashDB<DBHasher, trie_db::DBValue>,
+    child: &[u8],
+    depth: usize,
+    keys: &[(usize, &[u8])],
+    answers: &mut [Option<Box<[u8]>>],
+) {
+    match RlpNodeCodec::<DBHasher>::try_decode_hash(child) {
+        Some(hash) => {
+            let node = hash_db.get(&hash).expect("Missing trie node");
+            get_sorted(hash_db, &node, depth, keys, answers)
+        }
+        None => get_sorted(hash_db, child, depth, keys, answers),
     }
 }
 
@@ -69,6 +169,28 @@ impl AuthDB for MptDB {
             .map(|x| x.into_vec().into_boxed_slice())
     }
 
+    // The keys are sorted and looked up in one descent from the root, so each trie node on the
+    // shared prefix of several keys is loaded and decoded only once.
+    fn get_many(&self, keys: &[Vec<u8>]) -> Vec<Option<Box<[u8]>>> {
+        let mut answers = vec![None; keys.len()];
+        if self.root == RlpNodeCodec::<DBHasher>::hashed_null_node() {
+            return answers;
+        }
+        let db = self.db.borrow();
+        let hash_db = db.as_hash_db();
+
+        let mut sorted: Vec<(usize, &[u8])> = keys
+            .iter()
+            .enumerate()
+            .map(|(idx, key)| (idx, key.as_slice()))
+            .collect();
+        sorted.sort_by(|a, b| a.1.cmp(b.1));
+
+        let root = hash_db.get(&self.root).expect("Missing trie root");
+        get_sorted(hash_db, &root, 0, &sorted, &mut answers);
+        answers
+    }
+
     // This logic is in function `commit` in `ethcore/src/state/run` of OpenEthereum
     fn set(&mut self, key: Vec<u8>, value: Vec<u8>) {
         let mut db = self.db.borrow_mut();
@@ -78,35 +200,109 @@ impl AuthDB for MptDB {
         trie.insert(key.as_slice(), value.as_slice()).unwrap();
     }
 
-    // This logic is in function `commit` in `ethcore/src/state/run` of OpenEthereum
-    fn commit(&mut self, index: usize) {
-        self.epoch = index;
+    fn delete(&mut self, key: Vec<u8>) {
+        let mut db = self.db.borrow_mut();
+        let hash_db = db.as_hash_db_mut();
+
+        let mut trie = TrieDBMut::from_existing(hash_db, &mut self.root).unwrap();
+        trie.remove(key.as_slice()).unwrap();
+    }
 
+    // This logic is in function `commit` in `ethcore/src/state/run` of OpenEthereum
+    fn commit(&mut self, index: usize) -> StateRoot {
         let mut batch = DBTransaction::new();
         let mut db = self.db.borrow_mut();
 
         // The third parameter is not used in archive journal db. We feed an arbitrary data.
         db.journal_under(&mut batch, index as u64, &epoch_hash(index))
             .unwrap();
-        if let Some(old_index) = index.checked_sub(self.journal_epoch) {
+        self.recent_epochs.push_back(index);
+        if self.recent_epochs.len() > self.journal_epoch {
+            let old_index = self.recent_epochs.pop_front().unwrap();
             db.mark_canonical(&mut batch, old_index as u64, &epoch_hash(old_index))
                 .unwrap();
         }
         db.backing().write(batch).unwrap();
         db.flush();
 
-        if let Some(period) = self.print_root_period {
-            if index % period == 0 {
-                println!("Root {:?}", self.root);
+        if let Some(history_depth) = self.history_depth {
+            self.history_roots.push_back((index, self.root));
+            if self.history_roots.len() > history_depth {
+                self.history_roots.pop_front();
             }
         }
+
+        StateRoot::Hash(self.root)
+    }
+
+    fn support_history(&self) -> bool {
+        self.history_depth.is_some()
+    }
+
+    fn get_at(&self, epoch: usize, key: Vec<u8>) -> Result<Option<Box<[u8]>>, HistoryError> {
+        if self.history_depth.is_none() {
+            return Err(HistoryError::Unsupported);
+        }
+        let (_, root) = self
+            .history_roots
+            .iter()
+            .find(|(index, _)| *index == epoch)
+            .ok_or(HistoryError::Unavailable(epoch))?;
+        let db = self.db.borrow();
+        let hash_db = &db.as_hash_db();
+
+        let trie = TrieDB::new(hash_db, root).unwrap();
+        Ok(trie
+            .get(key.as_slice())
+            .unwrap()
+            .map(|x| x.into_vec().into_boxed_slice()))
+    }
+
+    // The proof is the list of trie nodes on the path of `key`.
+    fn prove(&mut self, key: Vec<u8>) -> Result<Proof, ProofError> {
+        let db = self.db.borrow();
+        let hash_db = &db.as_hash_db();
+
+        let trie = TrieDB::new(hash_db, &self.root).unwrap();
+        let mut recorder = Recorder::new();
+        trie.get_with(key.as_slice(), &mut recorder).unwrap();
+
+        let nodes: Vec<Vec<u8>> = recorder.drain().into_iter().map(|x| x.data).collect();
+        Ok(Proof(nodes.encode()))
+    }
+
+    // `root` is the hash of the root node.
+    fn verify(
+        &self,
+        key: Vec<u8>,
+        proof: &Proof,
+        root: &[u8],
+    ) -> Result<Option<Box<[u8]>>, ProofError> {
+        if root.len() != 32 {
+            return Err(ProofError::Invalid("Incorrect root length".to_string()));
+        }
+        let root = H256::from_slice(root);
+        let nodes = Vec::<Vec<u8>>::decode(&mut proof.0.as_slice())
+            .map_err(|e| ProofError::Invalid(format!("{:?}", e)))?;
+
+        let mut memory_db = MemoryDB::<DBHasher, trie_db::DBValue>::new(&NULL_RLP);
+        for node in nodes.iter() {
+            memory_db.insert(node.as_slice());
+        }
+        let hash_db: &dyn HashDB<DBHasher, trie_db::DBValue> = &memory_db;
+
+        let invalid = |e| ProofError::Invalid(format!("{:?}", e));
+        let trie = TrieDB::new(&hash_db, &root).map_err(invalid)?;
+        trie.get(key.as_slice())
+            .map(|value| value.map(|x| x.into_vec().into_boxed_slice()))
+            .map_err(invalid)
     }
 
     fn flush_all(&mut self) {
         let mut batch = DBTransaction::new();
         let mut db = self.db.borrow_mut();
-        for i in (0..self.journal_epoch).into_iter().rev() {
-            let index = self.epoch - i;
+        // Prune the recent epochs in order, so the backend holds the state of the latest epoch.
+        for index in self.recent_epochs.drain(..) {
             db.mark_canonical(&mut batch, index as u64, &epoch_hash(index))
                 .unwrap();
         }
@@ -137,12 +333,16 @@ impl MptCounter {
 }
 
 impl CounterTrait for MptCounter {
-    fn report(&mut self) -> String {
+    // The sizes of the in-memory structures, which depend on the journal database.
+    fn report(&mut self) -> Vec<Metric> {
         let mut sizes = BTreeMap::new();
         self.journal_db.borrow().get_sizes(&mut sizes);
-        format!(
-            "Recent backing size: {}",
-            sizes.get("db_overlay_recent_backing_size").unwrap()
-        )
+        sizes
+            .into_iter()
+            .map(|(name, size)| {
+                let name = name.trim_start_matches("db_").replace('-', "_");
+                Metric::gauge(format!("journal_{}", name), size as u64)
+            })
+            .collect()
     }
 }
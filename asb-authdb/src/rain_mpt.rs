@@ -1,40 +1,182 @@
 use std::sync::{Arc, RwLock};
 
-use authdb::AuthDB;
+use asb_options::Options;
+use asb_profile::{CounterTrait, Metric};
+#[cfg(feature = "thread-safe")]
+use authdb::AuthDBReader;
+use authdb::{AuthDB, HistoryError, Proof, ProofError, StateRoot};
 use kvdb::KeyValueDB;
+use parity_scale_codec::{Decode, Encode};
+use primitive_types::H256;
+#[cfg(feature = "thread-safe")]
+use rainblock_trie::CommittedReader;
 use rainblock_trie::MerklePatriciaTree;
 
-const CACHED_LEVEL: usize = 6;
-pub struct RainMpt(
-    RwLock<MerklePatriciaTree<CACHED_LEVEL>>,
-    Arc<dyn KeyValueDB>,
-);
+pub struct RainMpt {
+    trie: Arc<RwLock<MerklePatriciaTree>>,
+    backend: Arc<dyn KeyValueDB>,
+    // The index of the latest commit, only tracked for historical read.
+    latest_epoch: Option<usize>,
+}
 
-pub fn new(backend: Arc<dyn KeyValueDB>) -> RainMpt {
-    RainMpt(
-        RwLock::new(MerklePatriciaTree::<CACHED_LEVEL>::new(backend.clone())),
+// The top `rain_cached_level` levels of the trie are kept in memory.
+pub fn new(backend: Arc<dyn KeyValueDB>, opts: &Options) -> RainMpt {
+    let mut trie = MerklePatriciaTree::new(backend.clone(), opts.rain_cached_level);
+    if let Some(history_epochs) = opts.history_epochs {
+        trie.set_history_depth(history_epochs);
+    }
+    if opts.reader_threads.is_some() {
+        trie.keep_committed_root();
+    }
+    RainMpt {
+        trie: Arc::new(RwLock::new(trie)),
         backend,
-    )
+        latest_epoch: opts.history_epochs.map(|_| 0),
+    }
 }
 
 impl AuthDB for RainMpt {
     fn get(&self, key: Vec<u8>) -> Option<Box<[u8]>> {
-        self.0.write().unwrap().get(key).map(Vec::into_boxed_slice)
+        self.trie
+            .write()
+            .unwrap()
+            .get(key)
+            .map(Vec::into_boxed_slice)
+    }
+
+    fn get_many(&self, keys: &[Vec<u8>]) -> Vec<Option<Box<[u8]>>> {
+        let mut trie = self.trie.write().unwrap();
+        keys.iter()
+            .map(|key| trie.get(key.clone()).map(Vec::into_boxed_slice))
+            .collect()
     }
 
     fn set(&mut self, key: Vec<u8>, value: Vec<u8>) {
-        self.0.write().unwrap().put(key, value);
+        self.trie.write().unwrap().put(key, value);
+    }
+
+    // An empty key is never stored, so there is nothing to delete.
+    fn delete(&mut self, key: Vec<u8>) {
+        let _ = self.trie.write().unwrap().delete(key);
     }
 
-    fn commit(&mut self, _index: usize) {
-        self.0.write().unwrap().commit().unwrap();
+    fn commit(&mut self, index: usize) -> StateRoot {
+        let root = self.trie.write().unwrap().commit().unwrap();
+        if let Some(latest_epoch) = &mut self.latest_epoch {
+            *latest_epoch = index;
+        }
+        StateRoot::Hash(root)
+    }
+
+    fn support_history(&self) -> bool {
+        self.latest_epoch.is_some()
+    }
+
+    fn get_at(&self, epoch: usize, key: Vec<u8>) -> Result<Option<Box<[u8]>>, HistoryError> {
+        let latest_epoch = self.latest_epoch.ok_or(HistoryError::Unsupported)?;
+        let epochs_ago = latest_epoch
+            .checked_sub(epoch)
+            .ok_or(HistoryError::Unavailable(epoch))?;
+        self.trie
+            .read()
+            .unwrap()
+            .get_at(epochs_ago, key)
+            .map(|value| value.map(Vec::into_boxed_slice))
+            .map_err(|_| HistoryError::Unavailable(epoch))
+    }
+
+    // The proof is the list of trie nodes on the path of `key`.
+    fn prove(&mut self, key: Vec<u8>) -> Result<Proof, ProofError> {
+        let nodes = self
+            .trie
+            .write()
+            .unwrap()
+            .prove(key)
+            .map_err(ProofError::Invalid)?;
+        Ok(Proof(nodes.encode()))
+    }
+
+    // `root` is the hash of the root node.
+    fn verify(
+        &self,
+        key: Vec<u8>,
+        proof: &Proof,
+        root: &[u8],
+    ) -> Result<Option<Box<[u8]>>, ProofError> {
+        if root.len() != 32 {
+            return Err(ProofError::Invalid("Incorrect root length".to_string()));
+        }
+        let nodes = Vec::<Vec<u8>>::decode(&mut proof.0.as_slice())
+            .map_err(|e| ProofError::Invalid(format!("{:?}", e)))?;
+        MerklePatriciaTree::verify(H256::from_slice(root), key, &nodes)
+            .map(|value| value.map(Vec::into_boxed_slice))
+            .map_err(ProofError::Invalid)
     }
 
     fn backend(&self) -> Option<&dyn KeyValueDB> {
-        Some(&*self.1)
+        Some(&*self.backend)
+    }
+
+    // The trie nodes are only shared among threads with feature `thread-safe`. The reader sees the
+    // last committed state, and only waits for the commits.
+    #[cfg(feature = "thread-safe")]
+    fn reader(&self) -> Option<Box<dyn AuthDBReader>> {
+        Some(Box::new(Reader(
+            self.trie.read().unwrap().committed_reader(),
+        )))
     }
 
     fn flush_all(&mut self) {
-        self.0.write().unwrap().flush_all().unwrap()
+        self.trie.write().unwrap().flush_all().unwrap()
+    }
+}
+
+#[cfg(feature = "thread-safe")]
+struct Reader(CommittedReader);
+
+#[cfg(feature = "thread-safe")]
+impl AuthDBReader for Reader {
+    // An empty key is never stored, so it reads as absent.
+    fn get(&self, key: Vec<u8>) -> Option<Box<[u8]>> {
+        self.0.get(key).ok().flatten().map(Vec::into_boxed_slice)
+    }
+}
+
+pub struct RainCounter {
+    trie: Arc<RwLock<MerklePatriciaTree>>,
+    node_counts: (u64, u64),
+    // Measuring the cached nodes walks the top layers, so it is only done with `--stat-mem`.
+    stat_mem: bool,
+}
+
+impl RainCounter {
+    pub fn from_rain_mpt(rain_mpt: &RainMpt, opts: &Options) -> Self {
+        Self {
+            trie: rain_mpt.trie.clone(),
+            node_counts: (0, 0),
+            stat_mem: opts.stat_mem,
+        }
+    }
+}
+
+impl CounterTrait for RainCounter {
+    fn reset(&mut self) {
+        self.node_counts = self.trie.read().unwrap().node_counts();
+    }
+
+    fn report(&mut self) -> Vec<Metric> {
+        let trie = self.trie.read().unwrap();
+        let (loaded, exile) = trie.node_counts();
+        let mut metrics = vec![
+            Metric::counter("loaded_nodes", loaded - self.node_counts.0),
+            Metric::counter("exile_nodes", exile - self.node_counts.1),
+        ];
+        if self.stat_mem {
+            let (cached_nodes, cached_bytes) = trie.cached_memory();
+            metrics.push(Metric::gauge("cached_nodes", cached_nodes as u64));
+            metrics.push(Metric::gauge("cached_bytes", cached_bytes as u64));
+        }
+        self.node_counts = (loaded, exile);
+        metrics
     }
 }
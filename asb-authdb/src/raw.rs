@@ -1,6 +1,110 @@
-use kvdb::KeyValueDB;
+use asb_backend::BatchGet;
+use asb_options::Options;
+use authdb::{AuthDB, AuthDBReader, StateRoot, RAW_COL};
+use kvdb::{DBOp, DBTransaction, KeyValueDB};
+use std::collections::HashMap;
 use std::sync::Arc;
 
-pub fn new(backend: Arc<dyn KeyValueDB>) -> Arc<dyn KeyValueDB> {
-    backend
+pub struct Raw {
+    backend: Arc<dyn KeyValueDB>,
+    batch_get: Option<Arc<dyn BatchGet>>,
+    // The writes of the ongoing epoch, only buffered here with concurrent readers. The backend
+    // may expose its own write buffer to the readers before the commit.
+    pending: Option<HashMap<Vec<u8>, Option<Vec<u8>>>>,
+}
+
+pub fn new(
+    backend: Arc<dyn KeyValueDB>,
+    batch_get: Option<Arc<dyn BatchGet>>,
+    opts: &Options,
+) -> Raw {
+    Raw {
+        backend,
+        batch_get,
+        pending: opts.reader_threads.map(|_| HashMap::new()),
+    }
+}
+
+impl AuthDB for Raw {
+    fn get(&self, key: Vec<u8>) -> Option<Box<[u8]>> {
+        if let Some(value) = self.pending.as_ref().and_then(|pending| pending.get(&key)) {
+            return value.clone().map(Vec::into_boxed_slice);
+        }
+        AuthDB::get(&self.backend, key)
+    }
+
+    fn get_many(&self, keys: &[Vec<u8>]) -> Vec<Option<Box<[u8]>>> {
+        if self.pending.is_some() {
+            return keys.iter().map(|key| self.get(key.clone())).collect();
+        }
+        // Fall back to the point reads if the batched read fails, which report their own error.
+        let answers = self
+            .batch_get
+            .as_ref()
+            .and_then(|batch_get| batch_get.get_batch(RAW_COL, keys).ok());
+        match answers {
+            Some(answers) => answers
+                .into_iter()
+                .map(|x| x.map(Vec::into_boxed_slice))
+                .collect(),
+            None => AuthDB::get_many(&self.backend, keys),
+        }
+    }
+
+    fn set(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        if let Some(pending) = &mut self.pending {
+            pending.insert(key, Some(value));
+        } else {
+            AuthDB::set(&mut self.backend, key, value)
+        }
+    }
+
+    fn delete(&mut self, key: Vec<u8>) {
+        if let Some(pending) = &mut self.pending {
+            pending.insert(key, None);
+        } else {
+            AuthDB::delete(&mut self.backend, key)
+        }
+    }
+
+    fn commit(&mut self, index: usize) -> StateRoot {
+        if let Some(pending) = &mut self.pending {
+            let ops = pending
+                .drain()
+                .map(|(key, value)| match value {
+                    Some(value) => DBOp::Insert {
+                        col: RAW_COL,
+                        key: key.into(),
+                        value,
+                    },
+                    None => DBOp::Delete {
+                        col: RAW_COL,
+                        key: key.into(),
+                    },
+                })
+                .collect();
+            self.backend.write(DBTransaction { ops }).unwrap();
+        }
+        AuthDB::commit(&mut self.backend, index)
+    }
+
+    // The writes of an epoch are applied to the backend in one transaction at commit, so the
+    // reader sees the last committed state. Without `--reader-threads` the writes go to the
+    // backend directly, so there is no reader.
+    fn reader(&self) -> Option<Box<dyn AuthDBReader>> {
+        self.pending.as_ref()?;
+        Some(Box::new(RawReader(self.backend.clone())))
+    }
+
+    fn backend(&self) -> Option<&dyn KeyValueDB> {
+        Some(&*self.backend)
+    }
+}
+
+struct RawReader(Arc<dyn KeyValueDB>);
+
+impl AuthDBReader for RawReader {
+    fn get(&self, key: Vec<u8>) -> Option<Box<[u8]>> {
+        AuthDB::get(&self.0, key)
+    }
 }
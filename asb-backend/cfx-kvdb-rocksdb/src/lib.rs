@@ -644,6 +644,40 @@ impl Database {
         }
     }
 
+    /// Get the values of a batch of keys. It is still a loop of point lookups in RocksDB, but the
+    /// locks and the column family handle are acquired once for the whole batch.
+    pub fn get_batch(&self, col: u32, keys: &[Vec<u8>]) -> io::Result<Vec<Option<DBValue>>> {
+        match *self.db.read() {
+            Some(ref cfs) => {
+                let overlay = &self.overlay.read()[col as usize];
+                let flushing = &self.flushing.read()[col as usize];
+                let cf = cfs.get_cf(col as usize);
+                self.stats.tally_reads(keys.len() as u64);
+                let mut stat_bytes = 0;
+                let mut values = Vec::with_capacity(keys.len());
+                for key in keys {
+                    let value = match overlay
+                        .get(key.as_slice())
+                        .or_else(|| flushing.get(key.as_slice()))
+                    {
+                        Some(&KeyState::Insert(ref value)) => Some(value.clone()),
+                        Some(&KeyState::Delete) => None,
+                        None => cfs
+                            .db
+                            .get_cf_opt(cf, key, &self.read_opts)
+                            .map(|r| r.map(|v| v.to_vec()))
+                            .map_err(other_io_err)?,
+                    };
+                    stat_bytes += key.len() + value.as_ref().map_or(0, |v| v.len());
+                    values.push(value);
+                }
+                self.stats.tally_bytes_read(stat_bytes as u64);
+                Ok(values)
+            }
+            None => Ok(vec![None; keys.len()]),
+        }
+    }
+
     /// Close the database
     fn close(&self) {
         *self.db.write() = None;
@@ -67,6 +67,17 @@ impl PrometheusRegistry {
             .expect("prometheus identifiers must be are unique");
     }
 
+    /// Adds a new prometheus gauge with the specified floating point value
+    pub fn register_float_gauge(&mut self, name: &str, help: &str, value: f64) {
+        let name = format!("{}{}", self.prefix, name);
+        let g =
+            prometheus::Gauge::new(name.as_str(), help).expect("name and help must be non-empty");
+        g.set(value);
+        self.registry
+            .register(Box::new(g))
+            .expect("prometheus identifiers must be unique");
+    }
+
     /// Adds a new prometheus counter with the time spent in running the specified function
     pub fn register_optime<F: Fn() -> T, T>(&mut self, name: &str, f: &F) -> T {
         let start = Instant::now();
@@ -1,16 +1,24 @@
+use std::io;
 use std::path::Path;
 use std::sync::Arc;
 
 use cfx_kvdb_rocksdb::{CompactionProfile, Database, DatabaseConfig};
 
-use asb_options::Options;
+use asb_options::{Compaction, Options};
+use kvdb::DBValue;
+
+use crate::BatchGet;
 
 pub fn open(db_dir: &str, opts: &Options) -> Arc<Database> {
     let mut db_config = DatabaseConfig::with_columns(opts.num_cols());
 
     db_config.memory_budget = Some(opts.cache_size as usize);
-    db_config.compaction = CompactionProfile::auto(Path::new(db_dir));
-    db_config.disable_wal = false;
+    db_config.compaction = match opts.rocksdb_compaction {
+        Compaction::Auto => CompactionProfile::auto(Path::new(db_dir)),
+        Compaction::Ssd => CompactionProfile::ssd(),
+        Compaction::Hdd => CompactionProfile::hdd(),
+    };
+    db_config.disable_wal = opts.rocksdb_disable_wal;
     #[cfg(not(any(feature = "parity-backend", feature = "lmpts-backend")))]
     {
         db_config.enable_statistics = !opts.no_stat;
@@ -20,3 +28,9 @@ pub fn open(db_dir: &str, opts: &Options) -> Arc<Database> {
 
     Arc::new(db)
 }
+
+impl BatchGet for Database {
+    fn get_batch(&self, col: u32, keys: &[Vec<u8>]) -> io::Result<Vec<Option<DBValue>>> {
+        Database::get_batch(self, col, keys)
+    }
+}
@@ -1,5 +1,6 @@
 use asb_options::{Backend, Options};
-use kvdb::KeyValueDB;
+use kvdb::{DBValue, KeyValueDB};
+use std::io;
 use std::sync::Arc;
 
 #[cfg(not(feature = "parity-backend"))]
@@ -20,20 +21,35 @@ mod mdbx;
 #[cfg(feature = "parity-backend")]
 mod parity_kvdb_rocksdb;
 
+/// A backend which reads a batch of keys with less overhead than one `KeyValueDB::get` per key.
+pub trait BatchGet: Send + Sync {
+    fn get_batch(&self, col: u32, keys: &[Vec<u8>]) -> io::Result<Vec<Option<DBValue>>>;
+}
+
 pub fn backend(opts: &Options) -> Arc<dyn KeyValueDB> {
+    backend_with_batch_get(opts).0
+}
+
+/// Open the backend, together with its batched reader if the backend has one.
+pub fn backend_with_batch_get(opts: &Options) -> (Arc<dyn KeyValueDB>, Option<Arc<dyn BatchGet>>) {
     match opts.backend {
         Backend::RocksDB => {
             let db_dir = opts.db_dir.as_str();
             #[cfg(not(feature = "parity-backend"))]
             {
-                cfx_kvdb_rocksdb::open(db_dir, opts)
+                let db = cfx_kvdb_rocksdb::open(db_dir, opts);
+                (db.clone(), Some(db))
             }
             #[cfg(feature = "parity-backend")]
             {
-                parity_kvdb_rocksdb::open(db_dir, opts.num_cols())
+                (parity_kvdb_rocksdb::open(db_dir, opts), None)
             }
         }
-        Backend::InMemoryDB => Arc::new(kvdb_memorydb::create(opts.num_cols())),
-        Backend::MDBX => Arc::new(mdbx::open_database(opts)),
+        Backend::InMemoryDB => (in_memory_backend(opts.num_cols()), None),
+        Backend::MDBX => (Arc::new(mdbx::open_database(opts)), None),
     }
 }
+
+pub fn in_memory_backend(num_cols: u32) -> Arc<dyn KeyValueDB> {
+    Arc::new(kvdb_memorydb::create(num_cols))
+}
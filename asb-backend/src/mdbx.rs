@@ -31,7 +31,7 @@ pub fn open_database(opts: &Options) -> MdbxDatabase {
     builder.set_max_dbs(10);
     builder.set_geometry(Geometry {
         size: Some(0..4 * TB),
-        growth_step: Some(4 * GB as isize),
+        growth_step: Some((opts.mdbx_growth_step * GB) as isize),
         shrink_threshold: None,
         page_size: None,
     });
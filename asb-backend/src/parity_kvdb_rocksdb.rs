@@ -1,3 +1,4 @@
+use asb_options::{Compaction, Options};
 use kvdb::{DBOp, DBTransaction, DBValue, IoStats, IoStatsKind, KeyValueDB};
 use kvdb07::{
     DBOp as DBOp07, DBTransaction as DBTransaction07, IoStatsKind as IoStatsKind07,
@@ -8,10 +9,14 @@ use parity_util_mem::{MallocSizeOf, MallocSizeOfOps};
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 
-pub fn open(db_dir: &str, num_cols: u32) -> Arc<dyn KeyValueDB> {
-    let mut config = DatabaseConfig::with_columns(num_cols);
+pub fn open(db_dir: &str, opts: &Options) -> Arc<dyn KeyValueDB> {
+    let mut config = DatabaseConfig::with_columns(opts.num_cols());
     config.enable_statistics = false;
-    config.compaction = CompactionProfile::auto(&Path::new(db_dir));
+    config.compaction = match opts.rocksdb_compaction {
+        Compaction::Auto => CompactionProfile::auto(&Path::new(db_dir)),
+        Compaction::Ssd => CompactionProfile::ssd(),
+        Compaction::Hdd => CompactionProfile::hdd(),
+    };
 
     let db: WrappedDataBase = kvdb_rocksdb::Database::open(&config, db_dir)
         .unwrap()
@@ -0,0 +1,246 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// An experiment matrix loaded from a TOML file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The benchmark binary, by default `asb-main` next to this binary.
+    pub binary: Option<PathBuf>,
+    /// The directory of the logs, the results and the progress of the cases.
+    pub output: PathBuf,
+    /// The directory of the shared warmups. Without it, each case warms up by itself.
+    pub warmup: Option<String>,
+    /// The command wrapping the cases, e.g. a script running them under a memory limit.
+    #[serde(default)]
+    pub prefix: Vec<String>,
+    /// The command run before each case, e.g. `["sudo", "sysctl", "-w", "vm.drop_caches=3"]`.
+    #[serde(default)]
+    pub before_case: Vec<String>,
+    /// The time limit of a run in seconds.
+    pub timeout: Option<u64>,
+    /// The arguments passed to every case, but not to the warmups.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// The arguments passed to the warmups.
+    #[serde(default = "default_warmup_args")]
+    pub warmup_args: Vec<String>,
+    pub matrix: Matrix,
+    /// The cache sizes of some algorithms, replacing `matrix.cache_sizes`.
+    #[serde(default)]
+    pub algorithm_cache_sizes: BTreeMap<String, Vec<u64>>,
+    /// The arguments of some cases, passed after `args` in order.
+    #[serde(default)]
+    pub case_args: Vec<CaseArgs>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CaseArgs {
+    #[serde(default)]
+    pub when: Filter,
+    pub args: Vec<String>,
+}
+
+/// Selects the cases whose fields are in the given lists. An omitted list matches all the cases.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Filter {
+    /// The algorithms of the cases, e.g. `amt20` rather than `amt`.
+    #[serde(default)]
+    pub algorithms: Vec<String>,
+    #[serde(default)]
+    pub backends: Vec<String>,
+    #[serde(default)]
+    pub keys: Vec<String>,
+    #[serde(default)]
+    pub shards: Vec<usize>,
+    #[serde(default)]
+    pub cache_sizes: Vec<u64>,
+}
+
+impl Filter {
+    fn matches(&self, case: &Case) -> bool {
+        fn contains<T: PartialEq>(list: &[T], item: &T) -> bool {
+            list.is_empty() || list.contains(item)
+        }
+        contains(&self.algorithms, &case.algorithm)
+            && contains(&self.backends, &case.backend)
+            && contains(&self.keys, &case.keys)
+            && contains(&self.shards, &case.shards.unwrap_or(0))
+            && contains(&self.cache_sizes, &case.cache_size)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Matrix {
+    /// The algorithms of `-a`. `amt` picks the AMT depth from the number of keys, as
+    /// `ceil(log2(5 * keys))`, and skips the cases deeper than 26.
+    pub algorithms: Vec<String>,
+    #[serde(default = "default_backends")]
+    pub backends: Vec<String>,
+    /// The numbers of keys of `-k`, `real` for the real trace, or `fresh` for 10g keys without
+    /// warmup.
+    pub keys: Vec<String>,
+    /// The shards of LVMT, where 0 is the unsharded LVMT. The other algorithms ignore the shards
+    /// other than 0.
+    #[serde(default = "default_shards")]
+    pub shards: Vec<usize>,
+    #[serde(default = "default_cache_sizes")]
+    pub cache_sizes: Vec<u64>,
+    /// The cases left out of the matrix.
+    #[serde(default)]
+    pub exclude: Vec<Filter>,
+}
+
+fn default_warmup_args() -> Vec<String> {
+    vec!["--no-stat".into()]
+}
+
+fn default_backends() -> Vec<String> {
+    vec!["rocksdb".into()]
+}
+
+fn default_shards() -> Vec<usize> {
+    vec![0]
+}
+
+fn default_cache_sizes() -> Vec<u64> {
+    vec![1500]
+}
+
+/// A case of the matrix.
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub name: String,
+    pub algorithm: String,
+    pub backend: String,
+    pub keys: String,
+    pub shards: Option<usize>,
+    pub cache_size: u64,
+    /// The arguments selecting the task and the database, shared by the case and its warmup.
+    pub base_args: Vec<String>,
+    /// The arguments of `case_args` matching the case.
+    pub args: Vec<String>,
+    /// Whether the case starts from a warmup, false for `fresh`.
+    pub warmup: bool,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+    }
+
+    pub fn cases(&self) -> Result<Vec<Case>, String> {
+        let matrix = &self.matrix;
+        let mut cases = Vec::new();
+        for keys in matrix.keys.iter() {
+            for algorithm in matrix.algorithms.iter() {
+                let algorithm = match algorithm.as_str() {
+                    "amt" => {
+                        let depth = amt_depth(keys)?;
+                        if depth > 26 {
+                            println!(
+                                "Skip AMT with {} keys, which requires depth {}",
+                                keys, depth
+                            );
+                            continue;
+                        }
+                        format!("amt{}", depth)
+                    }
+                    algorithm => algorithm.to_string(),
+                };
+                let cache_sizes = self
+                    .algorithm_cache_sizes
+                    .get(&algorithm)
+                    .unwrap_or(&matrix.cache_sizes);
+                for &shards in matrix.shards.iter() {
+                    if shards > 0 && algorithm != "lvmt" {
+                        continue;
+                    }
+                    let shards = if shards > 0 { Some(shards) } else { None };
+                    for backend in matrix.backends.iter() {
+                        for &cache_size in cache_sizes.iter() {
+                            let mut case = Case::new(&algorithm, backend, keys, shards, cache_size);
+                            if matrix.exclude.iter().any(|filter| filter.matches(&case)) {
+                                continue;
+                            }
+                            for case_args in self.case_args.iter() {
+                                if case_args.when.matches(&case) {
+                                    case.args.extend_from_slice(&case_args.args);
+                                }
+                            }
+                            cases.push(case);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(cases)
+    }
+}
+
+impl Case {
+    fn new(
+        algorithm: &str,
+        backend: &str,
+        keys: &str,
+        shards: Option<usize>,
+        cache_size: u64,
+    ) -> Self {
+        let mut base_args = vec!["-a".into(), algorithm.into(), "-b".into(), backend.into()];
+        if keys == "real" {
+            base_args.push("--real-trace".into());
+        } else if keys == "fresh" {
+            base_args.extend(["-k".into(), "10g".into(), "--no-warmup".into()]);
+        } else {
+            base_args.extend(["-k".into(), keys.into()]);
+        }
+        if let Some(shards) = shards {
+            base_args.extend(["--shards".into(), shards.to_string()]);
+        }
+        base_args.extend(["--cache-size".into(), cache_size.to_string()]);
+
+        let shards_code = shards.map_or(String::new(), |shards| shards.to_string());
+        Self {
+            name: format!(
+                "{}{}_{}_{}_c{}",
+                algorithm, shards_code, backend, keys, cache_size
+            ),
+            algorithm: algorithm.into(),
+            backend: backend.into(),
+            keys: keys.into(),
+            shards,
+            cache_size,
+            base_args,
+            args: vec![],
+            warmup: keys != "fresh",
+        }
+    }
+}
+
+// The depth of AMT with 5 slots per key. The real trace has about 2 million keys, and a fresh case
+// is sized for 100 million keys.
+fn amt_depth(keys: &str) -> Result<usize, String> {
+    let keys = if keys == "real" {
+        2e6
+    } else if keys == "fresh" {
+        1e8
+    } else {
+        let (number, base) = match keys.chars().last() {
+            Some('k' | 'K') => (&keys[..keys.len() - 1], 1e3),
+            Some('m' | 'M') => (&keys[..keys.len() - 1], 1e6),
+            Some('g' | 'G') => (&keys[..keys.len() - 1], 1e9),
+            _ => (keys, 1.0),
+        };
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("Invalid number of keys {}", keys))?;
+        number * base
+    };
+    Ok((keys * 5.0).log2().ceil() as usize)
+}
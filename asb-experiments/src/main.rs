@@ -0,0 +1,381 @@
+mod config;
+
+use asb_options::Options;
+use config::{Case, Config};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    about = "Run an experiment matrix of the authenticated storage benchmarks",
+    rename_all = "kebab-case"
+)]
+struct ExperimentOptions {
+    #[structopt(help = "Experiment matrix in TOML")]
+    config: PathBuf,
+
+    #[structopt(long, help = "Print the commands without running them")]
+    dry_run: bool,
+
+    #[structopt(long, help = "Run the failed and timed out cases again")]
+    retry_failed: bool,
+}
+
+const PROGRESS_FILE: &str = "progress.txt";
+const SUMMARY_FILE: &str = "summary.csv";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Done,
+    Failed,
+    Timeout,
+}
+
+impl Status {
+    fn name(&self) -> &'static str {
+        match self {
+            Status::Done => "done",
+            Status::Failed => "failed",
+            Status::Timeout => "timeout",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "done" => Some(Status::Done),
+            "failed" => Some(Status::Failed),
+            "timeout" => Some(Status::Timeout),
+            _ => None,
+        }
+    }
+}
+
+/// The finished runs, appended to a file as `<status>\t<run>` lines, so an interrupted experiment
+/// resumes after the last finished run. A warmup is recorded as `warmup:<dir>`.
+struct Progress {
+    file: File,
+    runs: BTreeMap<String, Status>,
+}
+
+impl Progress {
+    fn open(path: &Path) -> Self {
+        let mut runs = BTreeMap::new();
+        if let Ok(file) = File::open(path) {
+            for line in BufReader::new(file).lines() {
+                let line = line.unwrap();
+                if let Some((status, run)) = line.split_once('\t') {
+                    if let Some(status) = Status::parse(status) {
+                        runs.insert(run.to_string(), status);
+                    }
+                }
+            }
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        Self { file, runs }
+    }
+
+    fn get(&self, run: &str) -> Option<Status> {
+        self.runs.get(run).copied()
+    }
+
+    fn record(&mut self, run: &str, status: Status) {
+        writeln!(self.file, "{}\t{}", status.name(), run).unwrap();
+        self.file.flush().unwrap();
+        self.runs.insert(run.to_string(), status);
+    }
+}
+
+struct Runner {
+    binary: PathBuf,
+    prefix: Vec<String>,
+    timeout: Option<Duration>,
+    dry_run: bool,
+}
+
+impl Runner {
+    fn run(&self, args: &[String], log: &Path, wrapped: bool) -> Status {
+        let mut command = vec![self.binary.display().to_string()];
+        command.extend_from_slice(args);
+        if wrapped && !self.prefix.is_empty() {
+            command = [&self.prefix[..], &command[..]].concat();
+        }
+        println!(">>> {}", command.join(" "));
+        if self.dry_run {
+            return Status::Done;
+        }
+
+        let log_file = File::create(log).unwrap();
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdout(log_file.try_clone().unwrap())
+            .stderr(log_file)
+            .spawn()
+            .unwrap_or_else(|e| panic!("Cannot start {}: {}", command[0], e));
+
+        let start = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait().unwrap() {
+                break if status.success() {
+                    Status::Done
+                } else {
+                    Status::Failed
+                };
+            }
+            if self
+                .timeout
+                .map_or(false, |timeout| start.elapsed() > timeout)
+            {
+                let _ = child.kill();
+                let _ = child.wait();
+                break Status::Timeout;
+            }
+            sleep(Duration::from_millis(200));
+        };
+        println!(
+            "<<< {} in {:.0}s, log in {}",
+            status.name(),
+            start.elapsed().as_secs_f64(),
+            log.display()
+        );
+        status
+    }
+
+    // Failures are reported but do not stop the case.
+    fn run_before(&self, command: &[String]) {
+        if command.is_empty() {
+            return;
+        }
+        println!(">>> {}", command.join(" "));
+        if self.dry_run {
+            return;
+        }
+        match Command::new(&command[0]).args(&command[1..]).status() {
+            Ok(status) if status.success() => {}
+            Ok(status) => println!("{} exits with {}", command[0], status),
+            Err(e) => println!("Cannot start {}: {}", command[0], e),
+        }
+    }
+}
+
+fn parse_options(args: &[String]) -> Options {
+    let argv = std::iter::once("asb-main").chain(args.iter().map(String::as_str));
+    Options::from_iter_with_config(argv)
+        .unwrap_or_else(|e| panic!("Invalid arguments {}: {}", args.join(" "), e.message))
+}
+
+fn main() {
+    let exp_opts = ExperimentOptions::from_args();
+    let config = Config::load(&exp_opts.config).unwrap_or_else(|e| panic!("{}", e));
+    let cases = config.cases().unwrap_or_else(|e| panic!("{}", e));
+
+    let runner = Runner {
+        binary: config
+            .binary
+            .clone()
+            .unwrap_or_else(|| std::env::current_exe().unwrap().with_file_name("asb-main")),
+        prefix: config.prefix.clone(),
+        timeout: config.timeout.map(Duration::from_secs),
+        dry_run: exp_opts.dry_run,
+    };
+
+    fs::create_dir_all(&config.output).unwrap();
+    if let Some(ref warmup) = config.warmup {
+        fs::create_dir_all(warmup).unwrap();
+    }
+    let mut progress = Progress::open(&config.output.join(PROGRESS_FILE));
+    let skip = |status: Option<Status>| match status {
+        Some(Status::Done) => true,
+        Some(_) => !exp_opts.retry_failed,
+        None => false,
+    };
+
+    // Check the arguments of all the cases before running any.
+    for case in cases.iter() {
+        parse_options(&[&case.base_args[..], &config.args[..], &case.args[..]].concat());
+    }
+
+    for case in cases.iter() {
+        if skip(progress.get(&case.name)) {
+            println!(
+                "Skip {}, which is {}",
+                case.name,
+                progress.get(&case.name).unwrap().name()
+            );
+            continue;
+        }
+
+        let mut args = [&case.base_args[..], &config.args[..], &case.args[..]].concat();
+        if let Some(warmup) = config.warmup.as_ref().filter(|_| case.warmup) {
+            let mut warmup_args = [&case.base_args[..], &config.warmup_args[..]].concat();
+            warmup_args.extend(["--warmup-to".into(), warmup.clone()]);
+            let warmup_dir = parse_options(&warmup_args).warmup_to().unwrap();
+            let run = format!("warmup:{}", warmup_dir);
+            let status = progress.get(&run);
+            if status != Some(Status::Done) || !Path::new(&warmup_dir).exists() {
+                if status != Some(Status::Done) && skip(status) {
+                    println!(
+                        "Skip {}, whose warmup {}",
+                        case.name,
+                        status.unwrap().name()
+                    );
+                    continue;
+                }
+                let log = config.output.join(format!("warmup_{}.log", case.name));
+                let status = runner.run(&warmup_args, &log, false);
+                if !runner.dry_run {
+                    progress.record(&run, status);
+                }
+                if status != Status::Done {
+                    continue;
+                }
+            }
+            args.extend(["--warmup-from".into(), warmup.clone()]);
+        }
+        let results = config.output.join(format!("{}.json", case.name));
+        args.extend(["--results-to".into(), results.display().to_string()]);
+
+        runner.run_before(&config.before_case);
+        let log = config.output.join(format!("{}.log", case.name));
+        let status = runner.run(&args, &log, true);
+        if !runner.dry_run {
+            progress.record(&case.name, status);
+        }
+    }
+
+    if !runner.dry_run {
+        summarize(&cases, &config.output, &progress);
+    }
+}
+
+const COLUMNS: [&str; 15] = [
+    "case",
+    "algorithm",
+    "backend",
+    "keys",
+    "shards",
+    "cache_size",
+    "status",
+    "epochs",
+    "elapsed_secs",
+    "ops_per_sec",
+    "read_amp",
+    "write_amp",
+    "get_p99_us",
+    "set_p99_us",
+    "commit_p99_us",
+];
+
+/// Collect the results of all the cases into a table, printed and written to `summary.csv`. The
+/// amplifications are averaged over the report windows.
+fn summarize(cases: &[Case], output: &Path, progress: &Progress) {
+    let mut rows = Vec::new();
+    for case in cases {
+        let mut row = vec![
+            case.name.clone(),
+            case.algorithm.clone(),
+            case.backend.clone(),
+            case.keys.clone(),
+            case.shards
+                .map_or(String::new(), |shards| shards.to_string()),
+            case.cache_size.to_string(),
+            progress
+                .get(&case.name)
+                .map_or("pending", |status| status.name())
+                .to_string(),
+        ];
+        row.extend(result_columns(&output.join(format!("{}.json", case.name))));
+        row.resize(COLUMNS.len(), String::new());
+        rows.push(row);
+    }
+
+    let mut csv = File::create(output.join(SUMMARY_FILE)).unwrap();
+    writeln!(csv, "{}", COLUMNS.join(",")).unwrap();
+    for row in rows.iter() {
+        writeln!(csv, "{}", row.join(",")).unwrap();
+    }
+
+    let widths: Vec<usize> = (0..COLUMNS.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain([COLUMNS[i].len()])
+                .max()
+                .unwrap()
+        })
+        .collect();
+    let format_row = |row: Vec<&str>| {
+        row.iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+    println!();
+    println!("{}", format_row(COLUMNS.to_vec()));
+    for row in rows.iter() {
+        println!("{}", format_row(row.iter().map(String::as_str).collect()));
+    }
+    println!("Summary written to {}", output.join(SUMMARY_FILE).display());
+}
+
+// The columns after `status`, empty if the case has no results.
+fn result_columns(path: &Path) -> Vec<String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return vec![],
+    };
+    let mut summary = Value::Null;
+    let mut amplifications = (vec![], vec![]);
+    for line in BufReader::new(file).lines() {
+        let record: Value = match line.ok().and_then(|line| serde_json::from_str(&line).ok()) {
+            Some(record) => record,
+            // A killed run may leave a partial line.
+            None => continue,
+        };
+        match record["type"].as_str() {
+            Some("window") => {
+                amplifications.0.extend(record["read_amp"].as_f64());
+                amplifications.1.extend(record["write_amp"].as_f64());
+            }
+            Some("summary") => summary = record,
+            _ => {}
+        }
+    }
+
+    let number = |value: &Value| {
+        value
+            .as_f64()
+            .map_or(String::new(), |x| format!("{:.2}", x))
+    };
+    let mean = |values: &Vec<f64>| {
+        if values.is_empty() {
+            String::new()
+        } else {
+            format!("{:.2}", values.iter().sum::<f64>() / values.len() as f64)
+        }
+    };
+    let mut columns = vec![
+        summary["epochs"]
+            .as_u64()
+            .map_or(String::new(), |x| x.to_string()),
+        number(&summary["elapsed_secs"]),
+        number(&summary["ops_per_sec"]),
+        mean(&amplifications.0),
+        mean(&amplifications.1),
+    ];
+    for op in ["get", "set", "commit"] {
+        columns.push(number(&summary["latency_us"][op]["p99"]));
+    }
+    columns
+}
@@ -0,0 +1,213 @@
+use std::fs;
+use toml::{Table, Value};
+
+/// The path of the last `--config` on the command line.
+pub(crate) fn config_path(args: &[String]) -> Option<String> {
+    let mut path = None;
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--config" {
+            path = iter.next().cloned();
+        } else if let Some(value) = arg.strip_prefix("--config=") {
+            path = Some(value.to_string());
+        }
+    }
+    path
+}
+
+/// Convert a configuration file into command line flags. A top-level key is the long name of a
+/// flag, and a key in a section, such as `[rocksdb]` or `[rain]`, is prefixed by the section name,
+/// so `compaction` in `[rocksdb]` is `--rocksdb-compaction`. A boolean flag is given if it is
+/// `true`, and its negation, such as `--no-print-root` or `--stat` for `no-stat`, if it is `false`,
+/// so that the command line can override the file either way.
+pub(crate) fn config_args(path: &str) -> Result<Vec<String>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Cannot read config {}: {}", path, e))?;
+    let table: Table = content
+        .parse()
+        .map_err(|e| format!("Invalid config {}: {}", path, e))?;
+
+    let mut args = Vec::new();
+    for (key, value) in table.iter() {
+        match value {
+            Value::Table(section) => {
+                for (name, value) in section.iter() {
+                    push_flag(&mut args, &format!("{}-{}", key, name), value)?;
+                }
+            }
+            value => push_flag(&mut args, key, value)?,
+        }
+    }
+    Ok(args)
+}
+
+fn push_flag(args: &mut Vec<String>, name: &str, value: &Value) -> Result<(), String> {
+    let flag = format!("--{}", name.replace('_', "-"));
+    if flag == "--config" {
+        return Err("A config file can not load another config file".into());
+    }
+    match value {
+        Value::Boolean(true) => args.push(flag),
+        Value::Boolean(false) => args.push(negate(&flag)),
+        Value::String(value) => args.extend([flag, value.clone()]),
+        Value::Integer(value) => args.extend([flag, value.to_string()]),
+        Value::Float(value) => args.extend([flag, value.to_string()]),
+        _ => return Err(format!("Unsupported value of {} in config", name)),
+    }
+    Ok(())
+}
+
+fn negate(flag: &str) -> String {
+    match flag.strip_prefix("--no-") {
+        Some(name) => format!("--{}", name),
+        None => format!("--no-{}", &flag[2..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AuthAlgo, Journal, Options};
+    use std::path::{Path, PathBuf};
+
+    fn write_config(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("asb-{}-{}.toml", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn parse(config: &Path, args: &[&str]) -> Options {
+        let config = config.to_str().unwrap();
+        let argv = ["asb-main", "--config", config].iter().chain(args.iter());
+        Options::from_iter_with_config(argv.map(|x| x.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_config_args() {
+        let path = write_config(
+            "args",
+            r#"
+algorithm = "rain"
+total-keys = "10m"
+cache_size = 4096
+grow-ratio = 0.5
+no-stat = true
+print-root = false
+
+[rain]
+cached-level = 5
+"#,
+        );
+        let args = config_args(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // The order of the keys is up to the TOML parser.
+        let expected: [&[&str]; 7] = [
+            &["--algorithm", "rain"],
+            &["--total-keys", "10m"],
+            &["--cache-size", "4096"],
+            &["--grow-ratio", "0.5"],
+            &["--no-stat"],
+            &["--no-print-root"],
+            &["--rain-cached-level", "5"],
+        ];
+        for flag in expected.iter() {
+            assert!(
+                args.windows(flag.len()).any(|x| x == *flag),
+                "Missing {:?} in {:?}",
+                flag,
+                args
+            );
+        }
+        assert_eq!(args.len(), expected.iter().map(|x| x.len()).sum::<usize>());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(config_args("/nonexistent/asb.toml").is_err());
+        for (name, content) in [
+            ("syntax", "algorithm = "),
+            ("array", "shards = [1, 2]"),
+            ("nested", "[rocksdb.compaction]\nlevel = 1"),
+            ("recursive", "config = \"other.toml\""),
+        ] {
+            let path = write_config(name, content);
+            assert!(config_args(path.to_str().unwrap()).is_err(), "{}", name);
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_override() {
+        let path = write_config(
+            "override",
+            r#"
+algorithm = "mpt:archive"
+no-stat = true
+enable-proof = false
+
+[mpt]
+journal = "refcounted"
+
+[rain]
+cached-level = 5
+"#,
+        );
+        let options = parse(&path, &[]);
+        assert!(options.no_stat && !options.enable_proof);
+        assert_eq!(options.algorithm, AuthAlgo::MPT(Journal::RefCounted, 0));
+        assert_eq!(options.rain_cached_level, 5);
+
+        // The command line overrides the file, including the boolean flags.
+        let options = parse(
+            &path,
+            &["--stat", "--enable-proof", "--rain-cached-level", "3"],
+        );
+        assert!(!options.no_stat && options.enable_proof);
+        assert_eq!(options.rain_cached_level, 3);
+        let options = parse(&path, &["-a", "mpt", "--mpt-journal-epoch", "16"]);
+        assert_eq!(options.algorithm, AuthAlgo::MPT(Journal::RefCounted, 16));
+
+        // The parser checks the cached levels of RainBlock.
+        let config = path.to_str().unwrap();
+        assert!(Options::from_iter_with_config([
+            "asb-main",
+            "--config",
+            config,
+            "-a",
+            "lvmt",
+            "--rain-cached-level",
+            "0"
+        ])
+        .is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_lvmt_only_merkle_root() {
+        let path = write_config("lvmt", "algorithm = \"lvmt\"");
+        assert!(parse(&path, &[]).lvmt_only_merkle_root());
+        assert!(!parse(&path, &["--enable-proof"]).lvmt_only_merkle_root());
+        assert!(!parse(&path, &["--no-lvmt-only-merkle-root"]).lvmt_only_merkle_root());
+        fs::remove_file(&path).unwrap();
+
+        let path = write_config("lvmt-section", "[lvmt]\nonly-merkle-root = false");
+        assert!(!parse(&path, &["-a", "lvmt"]).lvmt_only_merkle_root());
+        assert!(parse(&path, &["-a", "lvmt", "--lvmt-only-merkle-root"]).lvmt_only_merkle_root());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_lvmt_num_cols() {
+        let path = write_config("lvmt-cols", "algorithm = \"lvmt\"");
+        assert_eq!(parse(&path, &[]).num_cols(), 3);
+        // The slot keys are only kept with the proof shard.
+        assert_eq!(parse(&path, &["--enable-proof"]).num_cols(), 3);
+        assert_eq!(
+            parse(&path, &["--enable-proof", "--shards", "1"]).num_cols(),
+            4
+        );
+        assert_eq!(parse(&path, &["--history-epochs", "4"]).num_cols(), 6);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -2,11 +2,25 @@ extern crate structopt;
 #[macro_use]
 extern crate strum_macros;
 
+use serde::Serialize;
 pub use structopt::StructOpt;
 
-#[derive(Debug, StructOpt)]
-#[structopt(about = "Authenticated Storage Benchmarks", rename_all = "kebab-case")]
+mod config;
+
+#[derive(Debug, Clone, Serialize, StructOpt)]
+#[structopt(
+    about = "Authenticated Storage Benchmarks",
+    rename_all = "kebab-case",
+    // The flags from the configuration file come first, and the command line overrides them.
+    setting = structopt::clap::AppSettings::AllArgsOverrideSelf
+)]
 pub struct Options {
+    #[structopt(
+        long,
+        help = "Load the defaults of the flags from the given TOML file, overridden by the command line"
+    )]
+    pub config: Option<String>,
+
     #[structopt(short = "a", parse(try_from_str = parse_algo), long)]
     pub algorithm: AuthAlgo,
 
@@ -19,6 +33,43 @@ pub struct Options {
     #[structopt(long, default_value = "64")]
     pub seed: u64,
 
+    #[structopt(
+        long,
+        parse(try_from_str = parse_distribution),
+        default_value = "uniform",
+        help = "Key distribution of the random tasks: uniform, zipf[:skew], hotspot[:ops:keys] or latest[:skew]"
+    )]
+    pub distribution: Distribution,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_mix),
+        help = "Operation mix of the random tasks, e.g. read=0.5,write=0.3,insert=0.1,blind-write=0.1,read-only-keys=0.2"
+    )]
+    pub mix: Option<Mix>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_blockchain),
+        help = "Blocks of transfers and contract calls, e.g. accounts=1m,contracts=1k,slots=1k,txs=200,calls=0.5,slot-reads=4,slot-writes=2"
+    )]
+    pub blockchain: Option<Blockchain>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_ratio),
+        help = "Write fresh keys in the given fraction of operations, and report the state growth"
+    )]
+    pub grow_ratio: Option<f64>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_value_size),
+        default_value = "32",
+        help = "Value sizes of the random tasks: <size>, uniform:<min>:<max>, histogram:<file> or trace"
+    )]
+    pub value_size: ValueSize,
+
     #[structopt(long, default_value = "1500")]
     pub cache_size: u64,
 
@@ -46,21 +97,75 @@ pub struct Options {
     #[structopt(long = "trace", default_value = "./trace")]
     pub trace_dir: String,
 
-    #[structopt(long, help = "Use real trace")]
+    #[structopt(long, help = "Use real trace", overrides_with = "no_real_trace")]
     pub real_trace: bool,
 
-    #[structopt(long, help = "Disable backend stat")]
+    #[structopt(long, help = "Negate --real-trace", overrides_with = "real_trace")]
+    #[serde(skip)]
+    no_real_trace: bool,
+
+    #[structopt(
+        long,
+        help = "Record the warmup and the first --max-epoch epochs of the tasks to the given directory, without running them"
+    )]
+    pub record_trace: Option<String>,
+
+    #[structopt(long, help = "Replay the tasks recorded in the given directory")]
+    pub replay_trace: Option<String>,
+
+    #[structopt(long, help = "Disable backend stat", overrides_with = "stat")]
     pub no_stat: bool,
 
-    #[structopt(long, help = "Output the usage of memory")]
+    #[structopt(long, help = "Negate --no-stat", overrides_with = "no_stat")]
+    #[serde(skip)]
+    stat: bool,
+
+    #[structopt(
+        long,
+        help = "Output the usage of memory",
+        overrides_with = "no_stat_mem"
+    )]
     pub stat_mem: bool,
 
-    #[structopt(long, help = "No warmup")]
+    #[structopt(long, help = "Negate --stat-mem", overrides_with = "stat_mem")]
+    #[serde(skip)]
+    no_stat_mem: bool,
+
+    #[structopt(long, help = "No warmup", overrides_with = "warmup")]
     pub no_warmup: bool,
 
-    #[structopt(long, help = "Enable print root")]
+    #[structopt(long, help = "Negate --no-warmup", overrides_with = "no_warmup")]
+    #[serde(skip)]
+    warmup: bool,
+
+    #[structopt(long, help = "Enable print root", overrides_with = "no_print_root")]
     pub print_root: bool,
 
+    #[structopt(long, help = "Negate --print-root", overrides_with = "print_root")]
+    #[serde(skip)]
+    no_print_root: bool,
+
+    #[structopt(long, help = "Log the state root of each epoch to the given file")]
+    pub roots_to: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Write the options, the statistics of each report window and a summary to the given file as JSON lines"
+    )]
+    pub results_to: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Serve the metrics in the Prometheus text format on the given address"
+    )]
+    pub metrics_addr: Option<String>,
+
+    #[structopt(
+        long,
+        help = "Dump the metrics in the Prometheus text format to the given file"
+    )]
+    pub metrics_to: Option<String>,
+
     #[structopt(long)]
     pub warmup_to: Option<String>,
 
@@ -69,23 +174,202 @@ pub struct Options {
 
     #[structopt(long)]
     pub shards: Option<usize>,
+
+    #[structopt(
+        long,
+        help = "Maintain the data for proof generation",
+        overrides_with = "no_enable_proof"
+    )]
+    pub enable_proof: bool,
+
+    #[structopt(long, help = "Negate --enable-proof", overrides_with = "enable_proof")]
+    #[serde(skip)]
+    no_enable_proof: bool,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_ratio),
+        help = "Replace the given fraction of reads by proof requests"
+    )]
+    pub prove_ratio: Option<f64>,
+
+    #[structopt(
+        long,
+        help = "Verify the proofs of all the proof requests, rather than the first one of each epoch",
+        overrides_with = "no_verify_all_proofs"
+    )]
+    pub verify_all_proofs: bool,
+
+    #[structopt(
+        long,
+        help = "Negate --verify-all-proofs",
+        overrides_with = "verify_all_proofs"
+    )]
+    #[serde(skip)]
+    no_verify_all_proofs: bool,
+
+    #[structopt(long, help = "Read the states of the last given epochs")]
+    pub history_epochs: Option<usize>,
+
+    #[structopt(long, help = "Issue the reads in batches of the given size")]
+    pub read_batch: Option<usize>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_algo),
+        help = "Check the reads against the given algorithm on an in-memory backend"
+    )]
+    pub verify_against: Option<AuthAlgo>,
+
+    #[structopt(
+        long,
+        help = "Check that reopening the database at the given epoch reproduces the roots"
+    )]
+    pub restart_at: Option<usize>,
+
+    #[structopt(long, help = "Number of threads reading concurrently with the epochs")]
+    pub reader_threads: Option<usize>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_cached_level),
+        default_value = "6",
+        help = "Number of the top trie levels kept in memory by RainBlock, at least 1"
+    )]
+    pub rain_cached_level: usize,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_journal),
+        help = "Journal database of MPT, replacing the one of -a mpt:<journal>"
+    )]
+    pub mpt_journal: Option<Journal>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_num),
+        help = "Number of recent epochs MPT keeps before pruning, replacing the one of -a mpt:<journal>:<depth>"
+    )]
+    pub mpt_journal_epoch: Option<usize>,
+
+    #[structopt(
+        long,
+        help = "Only keep the Merkle root of LVMT, the default without --enable-proof",
+        overrides_with = "no_lvmt_only_merkle_root"
+    )]
+    lvmt_only_merkle_root: bool,
+
+    #[structopt(
+        long,
+        help = "Keep the Merkle tree of LVMT, the default with --enable-proof",
+        overrides_with = "lvmt_only_merkle_root"
+    )]
+    #[serde(skip)]
+    no_lvmt_only_merkle_root: bool,
+
+    #[structopt(
+        long,
+        default_value = "auto",
+        help = "Compaction profile of RocksDB: auto, ssd or hdd"
+    )]
+    pub rocksdb_compaction: Compaction,
+
+    #[structopt(
+        long,
+        help = "Disable the write-ahead log of RocksDB",
+        overrides_with = "no_rocksdb_disable_wal"
+    )]
+    pub rocksdb_disable_wal: bool,
+
+    #[structopt(
+        long,
+        help = "Negate --rocksdb-disable-wal",
+        overrides_with = "rocksdb_disable_wal"
+    )]
+    #[serde(skip)]
+    no_rocksdb_disable_wal: bool,
+
+    #[structopt(long, default_value = "4", help = "Growth step of the MDBX file in GB")]
+    pub mdbx_growth_step: usize,
 }
 
 impl Options {
+    /// Parse the command line as `from_args`, with the flags of the `--config` file as defaults.
+    pub fn from_args_with_config() -> Self {
+        Self::from_iter_with_config(std::env::args()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Parse the arguments as `from_iter_safe`, with the flags of the `--config` file as defaults.
+    pub fn from_iter_with_config<I, T>(args: I) -> Result<Self, structopt::clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        let mut args: Vec<String> = args.into_iter().map(Into::into).collect();
+        if let Some(path) = config::config_path(&args) {
+            let file_args = config::config_args(&path).map_err(|e| {
+                structopt::clap::Error::with_description(
+                    &e,
+                    structopt::clap::ErrorKind::InvalidValue,
+                )
+            })?;
+            let index = 1.min(args.len());
+            args.splice(index..index, file_args);
+        }
+        let mut options = Self::from_iter_safe(args)?;
+        if let AuthAlgo::MPT(journal, journal_epoch) = &mut options.algorithm {
+            *journal = options.mpt_journal.unwrap_or(*journal);
+            *journal_epoch = options.mpt_journal_epoch.unwrap_or(*journal_epoch);
+        }
+        Ok(options)
+    }
+
+    /// Whether LVMT only keeps the Merkle root of each epoch rather than the whole Merkle tree,
+    /// which is required by proof generation.
+    pub fn lvmt_only_merkle_root(&self) -> bool {
+        if self.lvmt_only_merkle_root || self.no_lvmt_only_merkle_root {
+            self.lvmt_only_merkle_root
+        } else {
+            !self.enable_proof
+        }
+    }
+
     fn warmup_dir(&self, input: &str) -> String {
-        let task_code = if !self.real_trace {
-            format!("{:e}", self.total_keys)
+        let task_code = if let Some(ref dir) = self.replay_trace {
+            let name = std::path::Path::new(dir).file_name().unwrap_or_default();
+            format!("trace-{}", name.to_string_lossy())
+        } else if !self.real_trace {
+            let mut task_code = match self.blockchain {
+                Some(ref chain) => format!("chain-{}", chain),
+                None => format!("{:e}", self.total_keys),
+            };
+            if self.distribution != Distribution::Uniform {
+                task_code += &format!("-{}", self.distribution);
+            }
+            if self.value_size != ValueSize::Fixed(32) {
+                task_code += &format!("-v{}", self.value_size);
+            }
+            task_code
         } else {
             "real".into()
         };
-        if self.algorithm != AuthAlgo::LVMT || self.shards.is_none() {
-            format!("{}/{:?}_{}/", input, self.algorithm, task_code)
+        let mut algo_code = if self.algorithm != AuthAlgo::LVMT || self.shards.is_none() {
+            self.algorithm.code()
         } else {
-            format!("{}/LVMT{}_{}/", input, self.shards.unwrap(), task_code)
+            let proof_code = if self.lvmt_only_merkle_root() {
+                ""
+            } else {
+                "-proof"
+            };
+            format!("LVMT{}{}", self.shards.unwrap(), proof_code)
+        };
+        if self.history_epochs.is_some() {
+            algo_code += "-history";
         }
+        format!("{}/{}_{}/", input, algo_code, task_code)
     }
     pub fn settings(&self) -> String {
-        format!("{:?},{:e}", self.algorithm, self.total_keys)
+        format!("{},{:e}", self.algorithm.code(), self.total_keys)
     }
     pub fn warmup_to(&self) -> Option<String> {
         self.warmup_to.as_ref().map(|x| self.warmup_dir(x))
@@ -94,34 +378,87 @@ impl Options {
         self.warmup_from.as_ref().map(|x| self.warmup_dir(x))
     }
 
+    // LVMT only opens its optional columns when needed, so the databases created without them
+    // still work. The slot keys are only kept for the proof shard.
     pub fn num_cols(&self) -> u32 {
         match self.algorithm {
-            AuthAlgo::LVMT => 3,
-            _ => 1,
+            AuthAlgo::LVMT => lvmt_db::lvmt_db::num_cols(
+                !self.lvmt_only_merkle_root() && self.shards.is_some(),
+                self.history_epochs.is_some(),
+            ),
+            _ => self.algorithm.num_cols(),
         }
     }
 }
 
-#[derive(Debug, Eq, PartialEq, EnumString)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, EnumString)]
 #[strum(serialize_all = "lowercase")]
 pub enum AuthAlgo {
     RAW,
     AMT(usize),
     LVMT,
-    MPT,
+    /// MPT on the given journal database, with the given number of recent epochs kept before
+    /// pruning.
+    MPT(Journal, usize),
     LMPTS,
     RAIN,
 }
 
+impl AuthAlgo {
+    /// The number of columns with all the optional columns.
+    pub fn num_cols(&self) -> u32 {
+        match self {
+            AuthAlgo::LVMT => lvmt_db::lvmt_db::NUM_COLS,
+            _ => 1,
+        }
+    }
+
+    // The default MPT keeps the code of the plain `MPT`, so the existing warmups still match.
+    fn code(&self) -> String {
+        match self {
+            AuthAlgo::MPT(Journal::OverlayRecent, 0) => "MPT".into(),
+            AuthAlgo::MPT(journal, depth) => format!("MPT-{}-{}", journal, depth),
+            algorithm => format!("{:?}", algorithm),
+        }
+    }
+}
+
+/// The journal databases of OpenEthereum, which decide how MPT prunes the stale trie nodes.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, EnumString, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Journal {
+    /// Keep all the trie nodes.
+    Archive,
+    /// Write the nodes to the backend immediately, and track their references in memory.
+    #[strum(to_string = "earlymerge", serialize = "light")]
+    EarlyMerge,
+    /// Keep the recent nodes in a memory overlay, flushed when their epoch is pruned.
+    #[default]
+    #[strum(to_string = "overlayrecent", serialize = "fast")]
+    OverlayRecent,
+    /// Count the references of the nodes in the backend.
+    #[strum(to_string = "refcounted", serialize = "basic")]
+    RefCounted,
+}
+
 fn parse_algo(s: &str) -> Result<AuthAlgo, String> {
     if s.len() >= 4 && &s[0..4] == "amt" {
         let depth = s[4..].parse::<usize>().map_err(|x| x.to_string())?;
         return Ok(AuthAlgo::AMT(depth));
     }
+    // `mpt:<journal>` or `mpt:<journal>:<depth>`, where the depth is the number of recent epochs
+    // kept before pruning.
+    if let Some(params) = s.strip_prefix("mpt:") {
+        let (journal, depth) = match params.split_once(':') {
+            Some((journal, depth)) => (journal, parse_num(depth)?),
+            None => (params, 0),
+        };
+        return Ok(AuthAlgo::MPT(parse_journal(journal)?, depth));
+    }
     return Ok(match s {
         "raw" => AuthAlgo::RAW,
         "lvmt" => AuthAlgo::LVMT,
-        "mpt" => AuthAlgo::MPT,
+        "mpt" => AuthAlgo::MPT(Journal::OverlayRecent, 0),
         "lmpts" => AuthAlgo::LMPTS,
         "rain" => AuthAlgo::RAIN,
         _ => {
@@ -130,6 +467,255 @@ fn parse_algo(s: &str) -> Result<AuthAlgo, String> {
     });
 }
 
+/// The distribution of the keys accessed by the random tasks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Distribution {
+    Uniform,
+    /// Zipfian with the given skew, the popular keys are spread over the key space.
+    Zipf(f64),
+    /// The given fraction of operations access the given fraction of keys.
+    Hotspot {
+        ops: f64,
+        keys: f64,
+    },
+    /// Zipfian with the given skew over the keys ordered from the latest inserted one.
+    Latest(f64),
+}
+
+impl std::fmt::Display for Distribution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Distribution::Uniform => write!(f, "uniform"),
+            Distribution::Zipf(skew) => write!(f, "zipf{}", skew),
+            Distribution::Hotspot { ops, keys } => write!(f, "hotspot{}-{}", ops, keys),
+            Distribution::Latest(skew) => write!(f, "latest{}", skew),
+        }
+    }
+}
+
+fn parse_distribution(s: &str) -> Result<Distribution, String> {
+    let mut parts = s.split(':');
+    let name = parts.next().unwrap();
+    let params = parts
+        .map(|x| x.parse::<f64>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<f64>, String>>()?;
+    let skew = |params: &[f64]| -> Result<f64, String> {
+        match *params {
+            [] => Ok(0.99),
+            [skew] if skew > 0.0 => Ok(skew),
+            [_] => Err("The skew must be positive".into()),
+            _ => Err("Too many parameters".into()),
+        }
+    };
+    Ok(match name {
+        "uniform" if params.is_empty() => Distribution::Uniform,
+        "zipf" => Distribution::Zipf(skew(&params)?),
+        "latest" => Distribution::Latest(skew(&params)?),
+        "hotspot" => {
+            let (ops, keys) = match *params {
+                [] => (0.8, 0.2),
+                [ops, keys] => (ops, keys),
+                _ => return Err("Hotspot takes the fractions of operations and keys".into()),
+            };
+            if !(0.0..=1.0).contains(&ops) || !(0.0..=1.0).contains(&keys) || keys == 0.0 {
+                return Err("The fractions must be in [0, 1], and the hot keys non-empty".into());
+            }
+            Distribution::Hotspot { ops, keys }
+        }
+        _ => return Err("Unrecognized distribution".into()),
+    })
+}
+
+/// The operation mix of a YCSB-style random task. The ratios of operations sum to 1.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mix {
+    /// Read an existing key.
+    pub read: f64,
+    /// Read an existing key, then write it.
+    pub write: f64,
+    /// Write a new key.
+    pub insert: f64,
+    /// Write an existing key without reading it.
+    pub blind_write: f64,
+    /// The fraction of the initial keys which are read but never written.
+    pub read_only_keys: f64,
+}
+
+fn parse_mix(s: &str) -> Result<Mix, String> {
+    let mut mix = Mix {
+        read: 0.0,
+        write: 0.0,
+        insert: 0.0,
+        blind_write: 0.0,
+        read_only_keys: 0.0,
+    };
+    for item in s.split(',') {
+        let (name, ratio) = item
+            .split_once('=')
+            .ok_or_else(|| format!("Expect name=ratio, got {}", item))?;
+        let ratio = parse_ratio(ratio)?;
+        match name {
+            "read" => mix.read = ratio,
+            "write" => mix.write = ratio,
+            "insert" => mix.insert = ratio,
+            "blind-write" => mix.blind_write = ratio,
+            "read-only-keys" => mix.read_only_keys = ratio,
+            _ => return Err(format!("Unrecognized operation {}", name)),
+        }
+    }
+    let total = mix.read + mix.write + mix.insert + mix.blind_write;
+    if (total - 1.0).abs() > 1e-6 {
+        return Err("The ratios of operations must sum to 1".into());
+    }
+    if mix.read_only_keys == 1.0 && (mix.write > 0.0 || mix.blind_write > 0.0) {
+        return Err("Writing existing keys requires some keys not read-only".into());
+    }
+    Ok(mix)
+}
+
+/// The shape of the blockchain task. Each block has `txs` transactions, and a transaction is either
+/// a transfer between two accounts, or a contract call from an account.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Blockchain {
+    /// The number of externally owned accounts.
+    pub accounts: usize,
+    pub contracts: usize,
+    /// The number of storage slots of each contract.
+    pub slots: usize,
+    /// The number of transactions per block.
+    pub txs: usize,
+    /// The fraction of transactions calling a contract.
+    pub calls: f64,
+    /// The number of slots read by a contract call.
+    pub slot_reads: usize,
+    /// The number of slots written by a contract call, among the slots it reads.
+    pub slot_writes: usize,
+}
+
+impl Blockchain {
+    pub fn total_keys(&self) -> usize {
+        self.accounts + self.contracts * (1 + self.slots)
+    }
+}
+
+// Only the initial state is encoded, the warmup does not depend on the transactions.
+impl std::fmt::Display for Blockchain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "a{:e}-c{:e}-s{:e}",
+            self.accounts, self.contracts, self.slots
+        )
+    }
+}
+
+fn parse_blockchain(s: &str) -> Result<Blockchain, String> {
+    let mut chain = Blockchain {
+        accounts: 1_000_000,
+        contracts: 1_000,
+        slots: 1_000,
+        txs: 200,
+        calls: 0.5,
+        slot_reads: 4,
+        slot_writes: 2,
+    };
+    for item in s.split(',').filter(|item| !item.is_empty()) {
+        let (name, value) = item
+            .split_once('=')
+            .ok_or_else(|| format!("Expect name=value, got {}", item))?;
+        match name {
+            "accounts" => chain.accounts = parse_num(value)?,
+            "contracts" => chain.contracts = parse_num(value)?,
+            "slots" => chain.slots = parse_num(value)?,
+            "txs" => chain.txs = parse_num(value)?,
+            "calls" => chain.calls = parse_ratio(value)?,
+            "slot-reads" => chain.slot_reads = parse_num(value)?,
+            "slot-writes" => chain.slot_writes = parse_num(value)?,
+            _ => return Err(format!("Unrecognized parameter {}", name)),
+        }
+    }
+    if chain.accounts < 2 {
+        return Err("A transfer requires at least two accounts".into());
+    }
+    if chain.txs == 0 {
+        return Err("A block requires some transactions".into());
+    }
+    if chain.calls > 0.0 && (chain.contracts == 0 || chain.slots == 0) {
+        return Err("Contract calls require some contracts with slots".into());
+    }
+    if chain.slot_writes > chain.slot_reads {
+        return Err("A contract call writes among the slots it reads".into());
+    }
+    Ok(chain)
+}
+
+/// The sizes of the values written by the random tasks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ValueSize {
+    Fixed(usize),
+    /// Uniform in the inclusive range.
+    Uniform(usize, usize),
+    /// A histogram loaded from a file of `size,weight` lines.
+    Histogram(String),
+    /// The histogram of the values in the initial state of the real trace.
+    Trace,
+}
+
+impl std::fmt::Display for ValueSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueSize::Fixed(size) => write!(f, "{}", size),
+            ValueSize::Uniform(min, max) => write!(f, "{}-{}", min, max),
+            ValueSize::Histogram(path) => {
+                let name = std::path::Path::new(path).file_stem().unwrap_or_default();
+                write!(f, "hist-{}", name.to_string_lossy())
+            }
+            ValueSize::Trace => write!(f, "trace"),
+        }
+    }
+}
+
+fn parse_value_size(s: &str) -> Result<ValueSize, String> {
+    // An empty value is a deletion in some authenticated storages.
+    fn size(s: &str) -> Result<usize, String> {
+        match parse_num(s)? {
+            0 => Err("The value size must be positive".into()),
+            size => Ok(size),
+        }
+    }
+    let parts: Vec<&str> = s.splitn(2, ':').collect();
+    Ok(match parts[..] {
+        [fixed] if fixed != "trace" => ValueSize::Fixed(size(fixed)?),
+        ["trace"] => ValueSize::Trace,
+        ["fixed", fixed] => ValueSize::Fixed(size(fixed)?),
+        ["uniform", range] => {
+            let (min, max) = range
+                .split_once(':')
+                .ok_or_else(|| "Expect uniform:<min>:<max>".to_string())?;
+            let (min, max) = (size(min)?, size(max)?);
+            if min > max {
+                return Err("The minimum size is larger than the maximum".into());
+            }
+            ValueSize::Uniform(min, max)
+        }
+        ["histogram", path] => ValueSize::Histogram(path.to_string()),
+        _ => return Err("Unrecognized value size".into()),
+    })
+}
+
+fn parse_journal(s: &str) -> Result<Journal, String> {
+    s.parse::<Journal>()
+        .map_err(|_| format!("Unrecognized journal {}", s))
+}
+
+fn parse_cached_level(s: &str) -> Result<usize, String> {
+    let level = s.parse::<usize>().map_err(|x| x.to_string())?;
+    if level == 0 {
+        return Err("RainBlock requires at least one cached level".into());
+    }
+    Ok(level)
+}
+
 fn parse_num(s: &str) -> Result<usize, String> {
     let base = match s
         .chars()
@@ -152,7 +738,24 @@ fn parse_num(s: &str) -> Result<usize, String> {
     Ok(base * num.parse::<usize>().map_err(|x| x.to_string())?)
 }
 
-#[derive(Debug, Eq, PartialEq, EnumString)]
+fn parse_ratio(s: &str) -> Result<f64, String> {
+    let ratio = s.parse::<f64>().map_err(|x| x.to_string())?;
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err("The ratio must be in [0, 1]".into());
+    }
+    Ok(ratio)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum Compaction {
+    /// Detect the profile from the disk of the database directory.
+    Auto,
+    Ssd,
+    Hdd,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, EnumString)]
 #[strum(serialize_all = "lowercase")]
 pub enum Backend {
     RocksDB,
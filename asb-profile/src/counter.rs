@@ -1,17 +1,23 @@
+use super::exporter::{BackendTotals, MetricsExporter, WindowMetrics};
+use super::latency::{LatencyHistogram, Op, QUANTILES};
 use super::profiler::Profiler;
-use asb_options::Options;
+use super::reader::ReaderStat;
+use super::results::{histogram_record, latency_record, metrics_record, ResultsWriter};
+use asb_options::{Backend, Options};
 use authdb::AuthDB;
 
 use kvdb::IoStatsKind;
 use lazy_static::lazy_static;
 use num_format::{Locale, WriteFormatted};
+use serde_json::{json, Value};
 #[cfg(any(target_os = "linux", target_os = "windows"))]
 use simple_process_stats::ProcessStats;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
-use std::sync::Mutex;
-use std::time::Instant;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 
 lazy_static! {
@@ -22,13 +28,40 @@ pub struct Reporter<'a> {
     pub start_time: Instant,
     total_read_count: usize,
     total_write_count: usize,
+    total_prove_count: usize,
+    total_unmeasured_time: Duration,
     log_file: Option<File>,
 
     round_start_time: Instant,
     round_start_read_count: usize,
     round_start_write_count: usize,
+    round_start_prove_count: usize,
 
     empty_reads: usize,
+    state_keys: Option<usize>,
+
+    prove_count: usize,
+    prove_time: Duration,
+    proof_bytes: usize,
+    prove_errors: usize,
+
+    verify_count: usize,
+    verify_time: Duration,
+    verify_failures: usize,
+    // The time of the checks in the report window, which is not a part of the tasks.
+    unmeasured_time: Duration,
+
+    latencies: [LatencyHistogram; 3],
+    total_latencies: [LatencyHistogram; 3],
+
+    results: Option<ResultsWriter>,
+    exporter: Option<MetricsExporter>,
+    backend_totals: BackendTotals,
+    windows: usize,
+    epochs: usize,
+
+    readers: Vec<Arc<ReaderStat>>,
+    round_start_reader_stats: Vec<(u64, u64)>,
 
     opts: &'a Options,
     counter: Box<dyn CounterTrait>,
@@ -54,10 +87,31 @@ impl<'a> Reporter<'a> {
             opts,
             counter: Box::new(Counter::default()),
             empty_reads: 0,
+            state_keys: None,
+            prove_count: 0,
+            prove_time: Duration::ZERO,
+            proof_bytes: 0,
+            prove_errors: 0,
+            verify_count: 0,
+            verify_time: Duration::ZERO,
+            verify_failures: 0,
+            unmeasured_time: Duration::ZERO,
+            latencies: Default::default(),
+            total_latencies: Default::default(),
+            results: None,
+            exporter: None,
+            backend_totals: BackendTotals::default(),
+            windows: 0,
+            epochs: 0,
+            readers: Vec::new(),
+            round_start_reader_stats: Vec::new(),
             total_read_count: 0,
             total_write_count: 0,
+            total_prove_count: 0,
+            total_unmeasured_time: Duration::ZERO,
             round_start_read_count: 0,
             round_start_write_count: 0,
+            round_start_prove_count: 0,
         }
     }
 
@@ -65,27 +119,80 @@ impl<'a> Reporter<'a> {
         self.counter = counter;
     }
 
+    pub fn set_readers(&mut self, readers: Vec<Arc<ReaderStat>>) {
+        self.readers = readers;
+    }
+
     pub fn start(&mut self) {
         self.start_time = Instant::now();
         self.round_start_time = Instant::now();
+        self.round_start_reader_stats = self.readers.iter().map(|x| x.snapshot()).collect();
+        self.readers.iter().for_each(|x| {
+            x.take_latencies();
+        });
         self.counter.reset();
+        if let Some(ref path) = self.opts.results_to {
+            self.results = Some(ResultsWriter::create(path, self.opts));
+        }
+        self.exporter = MetricsExporter::new(self.opts);
     }
 
     pub fn notify_empty_read(&mut self) {
         self.empty_reads += 1;
     }
 
+    /// Set the number of keys in the state, for the tasks that track the state growth.
+    pub fn notify_state_size(&mut self, keys: usize) {
+        self.state_keys = Some(keys);
+    }
+
+    pub fn notify_latency(&mut self, op: Op, time: Duration) {
+        self.latencies[op as usize].record(time);
+        self.total_latencies[op as usize].record(time);
+    }
+
+    pub fn notify_prove(&mut self, time: Duration, proof_size: usize) {
+        self.prove_count += 1;
+        self.total_prove_count += 1;
+        self.prove_time += time;
+        self.proof_bytes += proof_size;
+    }
+
+    /// Count a proof request failing on a valid key, e.g. a key outside the proof shard of LVMT.
+    pub fn notify_prove_error(&mut self) {
+        self.prove_errors += 1;
+        self.total_prove_count += 1;
+    }
+
+    /// Record the verification of a proof, and whether it proves the value in the state.
+    pub fn notify_verify(&mut self, time: Duration, valid: bool) {
+        self.verify_count += 1;
+        self.verify_time += time;
+        if !valid {
+            self.verify_failures += 1;
+        }
+    }
+
+    /// Exclude `time` from the per-operation time, for the checks out of the tasks.
+    pub fn notify_unmeasured(&mut self, time: Duration) {
+        self.unmeasured_time += time;
+        self.total_unmeasured_time += time;
+    }
+
     #[cfg(any(target_os = "linux", target_os = "windows"))]
-    pub async fn report_mem() {
+    pub async fn report_mem() -> Option<u64> {
         let process_stats = ProcessStats::get().await.unwrap();
         println!(
             "Memory {:>3.3} bytes",
             (process_stats.memory_usage_bytes as f64) / ((1 << 30) as f64)
         );
+        Some(process_stats.memory_usage_bytes)
     }
 
     #[cfg(not(any(target_os = "linux", target_os = "windows")))]
-    pub async fn report_mem() {}
+    pub async fn report_mem() -> Option<u64> {
+        None
+    }
 
     pub fn notify_epoch(
         &mut self,
@@ -103,6 +210,7 @@ impl<'a> Reporter<'a> {
 
         self.total_read_count += read_count;
         self.total_write_count += write_count;
+        self.epochs = epoch + 1;
 
         if (epoch + 1) % self.opts.report_epoch != 0 {
             return;
@@ -110,9 +218,11 @@ impl<'a> Reporter<'a> {
 
         let read_count = self.total_read_count - self.round_start_read_count;
         let write_count = self.total_write_count - self.round_start_write_count;
+        let prove_count = self.total_prove_count - self.round_start_prove_count;
 
-        let last = self.round_start_time.elapsed();
-        let avg_time = last.as_secs_f64() / (read_count + write_count) as f64;
+        // A proof request is an operation of the tasks, while the proof verification is not.
+        let last = self.round_start_time.elapsed() - self.unmeasured_time;
+        let avg_time = last.as_secs_f64() / (read_count + write_count + prove_count) as f64;
 
         let common = format!(
             "{:>6?}: {:>7.3?} s > {:>7} ops, {:>7.3?} us/op, {:>5} empty reads >",
@@ -123,9 +233,26 @@ impl<'a> Reporter<'a> {
             self.empty_reads,
         );
 
-        if opts.stat_mem {
-            RUNTIME.lock().unwrap().block_on(Self::report_mem());
-        }
+        let memory = if opts.stat_mem {
+            RUNTIME.lock().unwrap().block_on(Self::report_mem())
+        } else {
+            None
+        };
+
+        let db_bytes = if self.state_keys.is_some() && opts.backend != Backend::InMemoryDB {
+            Some(dir_size(Path::new(&opts.db_dir)))
+        } else {
+            None
+        };
+        let state_stat = match (self.state_keys, db_bytes) {
+            (Some(keys), Some(bytes)) => format!(
+                "{:>7} keys, {:>7.3} GB > ",
+                c(keys as u64),
+                bytes as f64 / (1 << 30) as f64
+            ),
+            (Some(keys), None) => format!("{:>7} keys > ", c(keys as u64)),
+            _ => "".into(),
+        };
 
         // let db_stat = {
         //     let stats = db.backend().io_stats(IoStatsKind::SincePrevious);
@@ -142,36 +269,205 @@ impl<'a> Reporter<'a> {
         //         bytes_per_write,
         //     )
         // };
-        let (stdout, fileout) = {
-            if let Some(backend) = db.backend() {
-                let stats = backend.io_stats(IoStatsKind::SincePrevious);
-                let ra = stats.reads as f64 / (read_count as f64);
-                let wa = stats.writes as f64 / (write_count as f64);
-                (
-                    format!("Read amp {:>6.3}, Write amp {:>6.3} > ", ra, wa),
-                    format!("{},{}", ra, wa),
-                )
-            } else {
-                ("".into(), "".into())
-            }
+        let backend_totals = &mut self.backend_totals;
+        let amplification = db.backend().map(|backend| {
+            let stats = backend.io_stats(IoStatsKind::SincePrevious);
+            backend_totals.reads += stats.reads;
+            backend_totals.cache_reads += stats.cache_reads;
+            backend_totals.writes += stats.writes;
+            backend_totals.bytes_read += stats.bytes_read;
+            backend_totals.bytes_written += stats.bytes_written;
+            let ra = stats.reads as f64 / (read_count as f64);
+            let wa = stats.writes as f64 / (write_count as f64);
+            (ra, wa)
+        });
+        let (stdout, fileout) = if let Some((ra, wa)) = amplification {
+            (
+                format!("Read amp {:>6.3}, Write amp {:>6.3} > ", ra, wa),
+                format!("{},{}", ra, wa),
+            )
+        } else {
+            ("".into(), ",".into())
         };
-        let customized = self.counter.report();
-        println!("{} {} {}", common, stdout, customized);
+        let us_per_proof = self.prove_time.as_secs_f64() / self.prove_count.max(1) as f64 * 1e6;
+        let bytes_per_proof = self.proof_bytes as f64 / self.prove_count.max(1) as f64;
+        let us_per_verify = self.verify_time.as_secs_f64() / self.verify_count.max(1) as f64 * 1e6;
+        let prove_stat = if prove_count > 0 {
+            format!(
+                "{:>7.3?} us/proof, {:>6.0} bytes/proof, {:>5} failed proofs > {:>7.3?} us/verify, {:>5} failed verifications > ",
+                us_per_proof, bytes_per_proof, self.prove_errors, us_per_verify, self.verify_failures,
+            )
+        } else {
+            "".into()
+        };
+        let latency_result = latency_record(&self.latencies);
+        let mut latency_stat = String::new();
+        // Each operation has a fixed set of columns in `timing.log`, left empty if not recorded.
+        let mut latency_fileout = String::new();
+        for op in Op::ALL {
+            let histogram = &self.latencies[op as usize];
+            if histogram.count() == 0 {
+                latency_fileout += &",".repeat(QUANTILES.len() + 1);
+                continue;
+            }
+            let micros = latency_micros(histogram);
+            latency_stat += &format!("{} {} us > ", op.name(), join_micros(&micros));
+            for x in micros {
+                latency_fileout += &format!(",{:.3}", x);
+            }
+        }
+
+        let round_secs = self.round_start_time.elapsed().as_secs_f64();
+        let reader_stats: Vec<(u64, u64)> = self.readers.iter().map(|x| x.snapshot()).collect();
+        let reader_throughputs: Vec<(f64, f64)> = reader_stats
+            .iter()
+            .zip(self.round_start_reader_stats.iter())
+            .map(|((reads, nanos), (start_reads, start_nanos))| {
+                let reads = reads - start_reads;
+                let avg_us = (nanos - start_nanos) as f64 / 1e3 / reads.max(1) as f64;
+                (reads as f64 / round_secs, avg_us)
+            })
+            .collect();
+        let reader_latencies: Vec<LatencyHistogram> =
+            self.readers.iter().map(|x| x.take_latencies()).collect();
+        let reader_stat: String = reader_throughputs
+            .iter()
+            .zip(reader_latencies.iter())
+            .enumerate()
+            .map(|(idx, ((ops, avg_us), histogram))| {
+                format!(
+                    "R{} {:>7} ops, {:>7.3?} us/op, {} us > ",
+                    idx,
+                    c(*ops as u64),
+                    avg_us,
+                    join_micros(&latency_micros(histogram))
+                )
+            })
+            .collect();
+        let metrics = self.counter.report();
+        let customized = metrics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "{} {}{}{}{}{} {}",
+            common, state_stat, prove_stat, latency_stat, reader_stat, stdout, customized
+        );
 
         if let Some(file) = &mut self.log_file {
             let _ = writeln!(
                 file,
-                "{},{},{:.3?},{}",
+                "{},{},{:.3?},{}{}",
                 self.opts.settings(),
                 (epoch + 1) / self.opts.report_epoch,
                 avg_time * 1e6,
-                fileout
+                fileout,
+                latency_fileout
             );
         }
+
+        if let Some(results) = &mut self.results {
+            let proofs = if prove_count > 0 {
+                json!({
+                    "count": self.prove_count,
+                    "errors": self.prove_errors,
+                    "us_per_proof": us_per_proof,
+                    "bytes_per_proof": bytes_per_proof,
+                    "verified": self.verify_count,
+                    "verify_failures": self.verify_failures,
+                    "us_per_verify": us_per_verify,
+                })
+            } else {
+                Value::Null
+            };
+            let readers: Vec<Value> = reader_throughputs
+                .iter()
+                .zip(reader_latencies.iter())
+                .map(|((ops, avg_us), histogram)| {
+                    json!({
+                        "ops_per_sec": ops,
+                        "us_per_op": avg_us,
+                        "latency_us": histogram_record(histogram),
+                    })
+                })
+                .collect();
+            results.write(json!({
+                "type": "window",
+                "window": (epoch + 1) / self.opts.report_epoch,
+                "epoch": epoch + 1,
+                "elapsed_secs": self.start_time.elapsed().as_secs_f64(),
+                "reads": read_count,
+                "writes": write_count,
+                "ops_per_sec": 1f64 / avg_time,
+                "us_per_op": avg_time * 1e6,
+                "empty_reads": self.empty_reads,
+                "read_amp": amplification.map(|x| x.0),
+                "write_amp": amplification.map(|x| x.1),
+                "latency_us": latency_result,
+                "proofs": proofs,
+                "readers": readers,
+                "memory_bytes": memory,
+                "state_keys": self.state_keys,
+                "db_bytes": db_bytes,
+                "metrics": metrics_record(&metrics),
+            }));
+        }
+
+        if let Some(exporter) = &mut self.exporter {
+            let window = WindowMetrics {
+                epochs: epoch + 1,
+                reads: self.total_read_count,
+                writes: self.total_write_count,
+                ops_per_sec: 1f64 / avg_time,
+                us_per_op: avg_time * 1e6,
+                empty_reads: self.empty_reads,
+                amplification,
+                backend: amplification.map(|_| self.backend_totals),
+                latencies: &self.latencies,
+                memory,
+                state_keys: self.state_keys,
+                db_bytes,
+            };
+            exporter.update(&window, &metrics);
+        }
+
+        self.windows += 1;
+        self.latencies.iter_mut().for_each(LatencyHistogram::reset);
         self.empty_reads = 0;
+        self.prove_count = 0;
+        self.prove_time = Duration::ZERO;
+        self.proof_bytes = 0;
+        self.prove_errors = 0;
+        self.verify_count = 0;
+        self.verify_time = Duration::ZERO;
+        self.verify_failures = 0;
+        self.unmeasured_time = Duration::ZERO;
         self.round_start_time = Instant::now();
+        self.round_start_reader_stats = reader_stats;
         self.round_start_read_count = self.total_read_count;
         self.round_start_write_count = self.total_write_count;
+        self.round_start_prove_count = self.total_prove_count;
+    }
+
+    /// Write the summary of the whole run to the results file.
+    pub fn finish(&mut self) {
+        if let Some(results) = &mut self.results {
+            let elapsed = self.start_time.elapsed().as_secs_f64();
+            let measured = (self.start_time.elapsed() - self.total_unmeasured_time).as_secs_f64();
+            let ops = self.total_read_count + self.total_write_count + self.total_prove_count;
+            results.write(json!({
+                "type": "summary",
+                "epochs": self.epochs,
+                "windows": self.windows,
+                "elapsed_secs": elapsed,
+                "reads": self.total_read_count,
+                "writes": self.total_write_count,
+                "proofs": self.total_prove_count,
+                "ops_per_sec": ops as f64 / measured,
+                "latency_us": latency_record(&self.total_latencies),
+            }));
+        }
     }
 
     pub fn collect_profiling(&self, profiler: Profiler) {
@@ -186,10 +482,99 @@ impl<'a> Reporter<'a> {
     }
 }
 
+/// A named metric reported by a `CounterTrait` for a report window.
+#[derive(Debug, Clone)]
+pub struct Metric {
+    pub name: String,
+    pub kind: MetricKind,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    /// The number of events in the report window.
+    Counter,
+    /// The current value of a quantity.
+    Gauge,
+    /// The ratio of two quantities in the report window.
+    Ratio,
+}
+
+impl Metric {
+    pub fn counter(name: impl Into<String>, value: u64) -> Self {
+        Self {
+            name: name.into(),
+            kind: MetricKind::Counter,
+            value: value as f64,
+        }
+    }
+
+    pub fn gauge(name: impl Into<String>, value: u64) -> Self {
+        Self {
+            name: name.into(),
+            kind: MetricKind::Gauge,
+            value: value as f64,
+        }
+    }
+
+    pub fn ratio(name: impl Into<String>, value: f64) -> Self {
+        Self {
+            name: name.into(),
+            kind: MetricKind::Ratio,
+            value,
+        }
+    }
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            MetricKind::Counter | MetricKind::Gauge => write!(f, "{} {}", self.name, self.value),
+            MetricKind::Ratio => write!(f, "{} {:.3}", self.name, self.value),
+        }
+    }
+}
+
+// The latency percentiles and the maximum latency in microseconds.
+fn latency_micros(histogram: &LatencyHistogram) -> Vec<f64> {
+    QUANTILES
+        .iter()
+        .map(|(_, q)| histogram.percentile(*q))
+        .chain(std::iter::once(histogram.max()))
+        .map(|x| x.as_secs_f64() * 1e6)
+        .collect()
+}
+
+fn join_micros(micros: &[f64]) -> String {
+    micros
+        .iter()
+        .map(|x| format!("{:.1}", x))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// The total size of the files under `path`.
+fn dir_size(path: &Path) -> u64 {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
 pub trait CounterTrait {
+    /// Called when the measurement starts, so that the first window excludes the warmup.
     fn reset(&mut self) {}
-    fn report(&mut self) -> String {
-        "".to_string()
+    /// The metrics of the report window since the last call.
+    fn report(&mut self) -> Vec<Metric> {
+        vec![]
     }
 }
 
@@ -0,0 +1,197 @@
+use super::counter::{Metric, MetricKind};
+use super::latency::{LatencyHistogram, Op, QUANTILES};
+use asb_options::Options;
+use stats::prometheus::{Encoder, TextEncoder};
+use stats::{PrometheusMetrics, PrometheusRegistry};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// The total operations of the backend since the measurement starts.
+#[derive(Default, Clone, Copy)]
+pub struct BackendTotals {
+    pub reads: u64,
+    pub cache_reads: u64,
+    pub writes: u64,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+
+/// The statistics of a report window, together with the totals of the run.
+pub struct WindowMetrics<'a> {
+    pub epochs: usize,
+    pub reads: usize,
+    pub writes: usize,
+    pub ops_per_sec: f64,
+    pub us_per_op: f64,
+    pub empty_reads: usize,
+    pub amplification: Option<(f64, f64)>,
+    pub backend: Option<BackendTotals>,
+    pub latencies: &'a [LatencyHistogram],
+    pub memory: Option<u64>,
+    pub state_keys: Option<usize>,
+    pub db_bytes: Option<u64>,
+}
+
+impl<'a> PrometheusMetrics for WindowMetrics<'a> {
+    fn prometheus_metrics(&self, p: &mut PrometheusRegistry) {
+        p.register_counter("epochs", "committed epochs", self.epochs as i64);
+        p.register_counter("reads", "authdb reads", self.reads as i64);
+        p.register_counter("writes", "authdb writes", self.writes as i64);
+        p.register_float_gauge("ops_per_sec", "window throughput", self.ops_per_sec);
+        p.register_float_gauge("us_per_op", "window average time per op", self.us_per_op);
+        p.register_gauge(
+            "empty_reads",
+            "window reads of absent keys",
+            self.empty_reads as i64,
+        );
+        if let Some((ra, wa)) = self.amplification {
+            p.register_float_gauge("read_amp", "window read amplification", ra);
+            p.register_float_gauge("write_amp", "window write amplification", wa);
+        }
+        if let Some(backend) = self.backend {
+            p.register_counter("backend_reads", "backend reads", backend.reads as i64);
+            p.register_counter(
+                "backend_cache_reads",
+                "backend cache reads",
+                backend.cache_reads as i64,
+            );
+            p.register_counter("backend_writes", "backend writes", backend.writes as i64);
+            p.register_counter(
+                "backend_bytes_read",
+                "backend bytes read",
+                backend.bytes_read as i64,
+            );
+            p.register_counter(
+                "backend_bytes_written",
+                "backend bytes written",
+                backend.bytes_written as i64,
+            );
+        }
+        for op in Op::ALL {
+            let histogram = &self.latencies[op as usize];
+            if histogram.count() == 0 {
+                continue;
+            }
+            for (name, quantile) in QUANTILES {
+                p.register_float_gauge(
+                    &format!("{}_latency_{}_us", op.name(), name),
+                    &format!("window {} latency of {}", name, op.name()),
+                    histogram.percentile(quantile).as_secs_f64() * 1e6,
+                );
+            }
+            p.register_float_gauge(
+                &format!("{}_latency_max_us", op.name()),
+                &format!("window max latency of {}", op.name()),
+                histogram.max().as_secs_f64() * 1e6,
+            );
+        }
+        if let Some(memory) = self.memory {
+            p.register_gauge("memory_bytes", "memory usage", memory as i64);
+        }
+        if let Some(keys) = self.state_keys {
+            p.register_gauge("state_keys", "keys in the state", keys as i64);
+        }
+        if let Some(bytes) = self.db_bytes {
+            p.register_gauge("db_bytes", "size of the database directory", bytes as i64);
+        }
+    }
+}
+
+/// Exposes the metrics of the latest report window in the Prometheus text format, through an
+/// HTTP endpoint, a file rewritten each window, or both.
+pub struct MetricsExporter {
+    text: Arc<Mutex<String>>,
+    file: Option<String>,
+    // Prometheus counters are cumulative, while a `CounterTrait` reports the window.
+    authdb_counters: BTreeMap<String, f64>,
+}
+
+impl MetricsExporter {
+    pub fn new(opts: &Options) -> Option<Self> {
+        if opts.metrics_addr.is_none() && opts.metrics_to.is_none() {
+            return None;
+        }
+        let text = Arc::new(Mutex::new(String::new()));
+        if let Some(addr) = &opts.metrics_addr {
+            serve(addr, text.clone());
+        }
+        if let Some(parent) = opts.metrics_to.as_ref().and_then(|x| Path::new(x).parent()) {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).unwrap();
+            }
+        }
+        Some(Self {
+            text,
+            file: opts.metrics_to.clone(),
+            authdb_counters: BTreeMap::new(),
+        })
+    }
+
+    pub fn update(&mut self, window: &WindowMetrics, metrics: &[Metric]) {
+        let mut registry = PrometheusRegistry::new("asb_".into());
+        window.prometheus_metrics(&mut registry);
+        for metric in metrics {
+            let name = format!("authdb_{}", metric.name);
+            match metric.kind {
+                MetricKind::Counter => {
+                    let total = self.authdb_counters.entry(name.clone()).or_default();
+                    *total += metric.value;
+                    registry.register_counter(&name, &metric.name, *total as i64);
+                }
+                MetricKind::Gauge | MetricKind::Ratio => {
+                    registry.register_float_gauge(&name, &metric.name, metric.value);
+                }
+            }
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&registry.registry().gather(), &mut buffer)
+            .unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        if let Some(path) = &self.file {
+            // Replace the file at once, so that a scraper never reads a partial file.
+            // A failed write only loses this window, the benchmark goes on.
+            let tmp_path = format!("{}.tmp", path);
+            if let Err(e) = fs::write(&tmp_path, &text).and_then(|_| fs::rename(&tmp_path, path)) {
+                println!("Fail to write metrics to {}: {:?}", path, e);
+            }
+        }
+        *self.text.lock().unwrap() = text;
+    }
+}
+
+// Answer every request on `addr` with the latest metrics, regardless of the path.
+fn serve(addr: &str, text: Arc<Mutex<String>>) {
+    let listener = TcpListener::bind(addr)
+        .unwrap_or_else(|e| panic!("Cannot serve metrics on {}: {}", addr, e));
+    println!("Serve metrics on http://{}/metrics", addr);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            // A client which never sends the request must not block the other scrapers.
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+            let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let body = text.lock().unwrap().clone();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+}
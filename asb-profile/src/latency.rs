@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+// Values below `2 * SUB_BUCKETS` nanoseconds are recorded exactly. Each larger power of two range
+// is split into `SUB_BUCKETS` buckets, so the relative error is below 1 / SUB_BUCKETS.
+const SUB_BUCKET_BITS: u32 = 6;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const NUM_BUCKETS: usize = 2 * SUB_BUCKETS + (64 - SUB_BUCKET_BITS as usize - 1) * SUB_BUCKETS;
+
+// The reported percentiles of each operation, besides the maximum.
+pub(crate) const QUANTILES: [(&str, f64); 4] =
+    [("p50", 0.5), ("p95", 0.95), ("p99", 0.99), ("p999", 0.999)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Get,
+    Set,
+    Commit,
+}
+
+impl Op {
+    pub const ALL: [Op; 3] = [Op::Get, Op::Set, Op::Commit];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Op::Get => "get",
+            Op::Set => "set",
+            Op::Commit => "commit",
+        }
+    }
+}
+
+/// A log-linear latency histogram in the style of HDR histogram, with a fixed memory cost.
+pub struct LatencyHistogram {
+    buckets: Vec<u64>,
+    count: u64,
+    max: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0; NUM_BUCKETS],
+            count: 0,
+            max: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, time: Duration) {
+        let nanos = time.as_nanos() as u64;
+        self.buckets[bucket_index(nanos)] += 1;
+        self.count += 1;
+        self.max = self.max.max(nanos);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max)
+    }
+
+    /// The smallest recorded value such that `quantile` of the records are not larger than it,
+    /// rounded up to the bucket boundary.
+    pub fn percentile(&self, quantile: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((quantile * self.count as f64).ceil() as u64).clamp(1, self.count);
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Duration::from_nanos(bucket_upper_bound(index).min(self.max));
+            }
+        }
+        self.max()
+    }
+
+    pub fn reset(&mut self) {
+        self.buckets.iter_mut().for_each(|x| *x = 0);
+        self.count = 0;
+        self.max = 0;
+    }
+}
+
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < 2 * SUB_BUCKETS as u64 {
+        return nanos as usize;
+    }
+    let shift = 64 - nanos.leading_zeros() - SUB_BUCKET_BITS - 1;
+    let sub_bucket = (nanos >> shift) as usize - SUB_BUCKETS;
+    SUB_BUCKETS + shift as usize * SUB_BUCKETS + sub_bucket
+}
+
+fn bucket_upper_bound(index: usize) -> u64 {
+    if index < 2 * SUB_BUCKETS {
+        return index as u64;
+    }
+    let shift = (index / SUB_BUCKETS - 1) as u32;
+    let sub_bucket = (index % SUB_BUCKETS + SUB_BUCKETS) as u64;
+    (sub_bucket << shift) + ((1 << shift) - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds() {
+        for nanos in (0..100_000u64).chain([u64::MAX / 3, u64::MAX]) {
+            let index = bucket_index(nanos);
+            assert!(index < NUM_BUCKETS);
+            let upper = bucket_upper_bound(index);
+            assert!(upper >= nanos);
+            assert!(upper - nanos <= nanos >> SUB_BUCKET_BITS);
+        }
+    }
+
+    #[test]
+    fn test_percentile() {
+        let mut histogram = LatencyHistogram::default();
+        for micros in 1..=1000 {
+            histogram.record(Duration::from_micros(micros));
+        }
+        let p50 = histogram.percentile(0.5).as_nanos() as f64;
+        assert!((p50 / 500_000.0 - 1.0).abs() < 0.02);
+        let p99 = histogram.percentile(0.99).as_nanos() as f64;
+        assert!((p99 / 990_000.0 - 1.0).abs() < 0.02);
+        assert_eq!(histogram.percentile(1.0), Duration::from_micros(1000));
+        assert_eq!(histogram.max(), Duration::from_micros(1000));
+
+        histogram.reset();
+        assert_eq!(histogram.count(), 0);
+        assert_eq!(histogram.percentile(0.5), Duration::ZERO);
+    }
+}
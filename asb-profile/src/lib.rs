@@ -1,5 +1,11 @@
 mod counter;
+mod exporter;
+mod latency;
 mod profiler;
+mod reader;
+mod results;
 
-pub use counter::{Counter, CounterTrait, Reporter};
+pub use counter::{Counter, CounterTrait, Metric, MetricKind, Reporter};
+pub use latency::{LatencyHistogram, Op};
 pub use profiler::Profiler;
+pub use reader::ReaderStat;
@@ -0,0 +1,36 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::latency::LatencyHistogram;
+
+/// The operation statistics of a concurrent reader thread. The reader thread records, and the
+/// reporter takes a snapshot each round.
+#[derive(Default)]
+pub struct ReaderStat {
+    reads: AtomicU64,
+    nanos: AtomicU64,
+    // The latencies of the current round, only contended when the reporter takes them.
+    latencies: Mutex<LatencyHistogram>,
+}
+
+impl ReaderStat {
+    pub fn record(&self, time: Duration) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.nanos
+            .fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+        self.latencies.lock().unwrap().record(time);
+    }
+
+    pub(crate) fn snapshot(&self) -> (u64, u64) {
+        (
+            self.reads.load(Ordering::Relaxed),
+            self.nanos.load(Ordering::Relaxed),
+        )
+    }
+
+    // The latencies since the last call, which starts a new round.
+    pub(crate) fn take_latencies(&self) -> LatencyHistogram {
+        std::mem::take(&mut *self.latencies.lock().unwrap())
+    }
+}
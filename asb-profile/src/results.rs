@@ -0,0 +1,62 @@
+use super::counter::Metric;
+use super::latency::{LatencyHistogram, Op, QUANTILES};
+use asb_options::Options;
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::Write;
+
+/// Writes the results of a run as JSON lines: a `config` record with the options, a `window`
+/// record for each report window and a `summary` record at the end.
+pub struct ResultsWriter {
+    file: File,
+}
+
+impl ResultsWriter {
+    pub fn create(path: &str, opts: &Options) -> Self {
+        let mut writer = Self {
+            file: File::create(path).unwrap(),
+        };
+        writer.write(json!({ "type": "config", "options": opts }));
+        writer
+    }
+
+    pub fn write(&mut self, record: Value) {
+        writeln!(self.file, "{}", record).unwrap();
+        self.file.flush().unwrap();
+    }
+}
+
+/// The latency percentiles and the maximum latency of each operation in microseconds.
+pub fn latency_record(latencies: &[LatencyHistogram]) -> Value {
+    let mut record = Map::new();
+    for op in Op::ALL {
+        let histogram = &latencies[op as usize];
+        if histogram.count() == 0 {
+            continue;
+        }
+        record.insert(op.name().into(), histogram_record(histogram));
+    }
+    Value::Object(record)
+}
+
+/// The latency percentiles and the maximum latency of a histogram in microseconds.
+pub fn histogram_record(histogram: &LatencyHistogram) -> Value {
+    let mut stat = Map::new();
+    for (name, quantile) in QUANTILES {
+        stat.insert(
+            name.into(),
+            json!(histogram.percentile(quantile).as_secs_f64() * 1e6),
+        );
+    }
+    stat.insert("max".into(), json!(histogram.max().as_secs_f64() * 1e6));
+    Value::Object(stat)
+}
+
+pub fn metrics_record(metrics: &[Metric]) -> Value {
+    Value::Object(
+        metrics
+            .iter()
+            .map(|metric| (metric.name.clone(), json!(metric.value)))
+            .collect(),
+    )
+}
@@ -0,0 +1,417 @@
+//! Converts storage access logs into the trace format of `--replay-trace`.
+//!
+//! Each record of a log has an operation (`read`, `write` or `delete`), a key, a value or a value
+//! size for writes, and a block number. The records of consecutive blocks become the epochs of the
+//! trace, so the blocks must be in order.
+
+use asb_tasks::trace_file::{TraceWriter, TASKS_FILE, WARMUP_FILE};
+use asb_tasks::{Event, Events};
+use rand::prelude::*;
+use rand_pcg::Pcg64;
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    about = "Convert CSV or JSON-lines access logs into a trace for --replay-trace",
+    rename_all = "kebab-case"
+)]
+struct ImportOptions {
+    #[structopt(help = "Access log of the tasks")]
+    input: String,
+
+    #[structopt(help = "Output trace directory")]
+    output: String,
+
+    #[structopt(
+        long,
+        help = "Access log of the warmup, e.g. the writes of the initial state"
+    )]
+    warmup: Option<String>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_format),
+        help = "Log format: csv or jsonl, detected from the file extension by default"
+    )]
+    format: Option<LogFormat>,
+
+    #[structopt(
+        long,
+        parse(try_from_str = parse_key_hash),
+        default_value = "keccak",
+        help = "Key hashing: keccak hashes the keys into 32 bytes like the random tasks, none keeps the keys"
+    )]
+    key_hash: KeyHash,
+
+    #[structopt(
+        long,
+        default_value = "1",
+        help = "Number of blocks merged into an epoch"
+    )]
+    blocks_per_epoch: u64,
+
+    #[structopt(
+        long,
+        default_value = "64",
+        help = "Seed of the values generated from sizes"
+    )]
+    seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    Csv,
+    JsonLines,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyHash {
+    Keccak,
+    None,
+}
+
+fn parse_format(s: &str) -> Result<LogFormat, String> {
+    match s {
+        "csv" => Ok(LogFormat::Csv),
+        "jsonl" | "json" => Ok(LogFormat::JsonLines),
+        _ => Err("Unrecognized log format".into()),
+    }
+}
+
+fn parse_key_hash(s: &str) -> Result<KeyHash, String> {
+    match s {
+        "keccak" => Ok(KeyHash::Keccak),
+        "none" => Ok(KeyHash::None),
+        _ => Err("Unrecognized key hashing".into()),
+    }
+}
+
+/// A record of the access log. In CSV logs, the header names the columns `op`, `key`, `value` and
+/// `block`. A value is either a hex string with the `0x` prefix, or a size in bytes.
+#[derive(Deserialize)]
+struct LogRecord {
+    op: String,
+    key: String,
+    #[serde(default)]
+    value: Option<LogValue>,
+    block: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LogValue {
+    Size(usize),
+    Text(String),
+}
+
+struct Importer<'a> {
+    opts: &'a ImportOptions,
+    random: Pcg64,
+}
+
+impl<'a> Importer<'a> {
+    fn import(&mut self, input: &str, output: &Path) {
+        let format = self.opts.format.unwrap_or_else(|| detect_format(input));
+        let file = File::open(input).unwrap_or_else(|e| panic!("Cannot open {}: {}", input, e));
+        let records: Box<dyn Iterator<Item = (usize, LogRecord)> + '_> = match format {
+            LogFormat::Csv => Box::new(
+                csv::Reader::from_reader(file)
+                    .into_deserialize()
+                    .enumerate()
+                    // The header is the first line.
+                    .map(|(index, record)| (index + 2, record))
+                    .map(move |(line, record)| {
+                        let record = record.unwrap_or_else(|e| {
+                            panic!("Invalid record at {}:{}: {}", input, line, e)
+                        });
+                        (line, record)
+                    }),
+            ),
+            LogFormat::JsonLines => Box::new(
+                BufReader::new(file)
+                    .lines()
+                    .enumerate()
+                    .map(|(index, line)| (index + 1, line.unwrap()))
+                    .filter(|(_, line)| !line.trim().is_empty())
+                    .map(move |(line, content)| {
+                        let record = serde_json::from_str(&content).unwrap_or_else(|e| {
+                            panic!("Invalid record at {}:{}: {}", input, line, e)
+                        });
+                        (line, record)
+                    }),
+            ),
+        };
+
+        let mut writer = TraceWriter::create(output).unwrap();
+        let mut current: Option<u64> = None;
+        let mut events = Vec::new();
+        let mut epochs = 0;
+        let mut total_events = 0;
+        for (line, record) in records {
+            let epoch = record.block / self.opts.blocks_per_epoch;
+            match current {
+                Some(current) if epoch < current => {
+                    panic!("Block {} out of order at {}:{}", record.block, input, line)
+                }
+                Some(current) if epoch == current => {}
+                Some(_) => {
+                    writer
+                        .write_epoch(&Events(std::mem::take(&mut events)))
+                        .unwrap();
+                    epochs += 1;
+                }
+                None => {}
+            }
+            current = Some(epoch);
+
+            let event = self
+                .event(record)
+                .unwrap_or_else(|e| panic!("Invalid record at {}:{}: {}", input, line, e));
+            events.push(event);
+            total_events += 1;
+        }
+        if !events.is_empty() {
+            writer.write_epoch(&Events(events)).unwrap();
+            epochs += 1;
+        }
+        writer.finish().unwrap();
+        println!(
+            "Imported {} events in {} epochs from {}",
+            total_events, epochs, input
+        );
+    }
+
+    fn event(&mut self, record: LogRecord) -> Result<Event, String> {
+        let key = self.key(&record.key)?;
+        Ok(match record.op.to_lowercase().as_str() {
+            "read" | "get" | "r" => Event::Read(key),
+            "write" | "put" | "set" | "w" => {
+                let value = match record.value {
+                    Some(LogValue::Size(size)) => self.random_value(size),
+                    Some(LogValue::Text(text)) => match text.parse::<usize>() {
+                        Ok(size) => self.random_value(size),
+                        Err(_) => decode_hex(&text)?,
+                    },
+                    None => return Err("A write requires a value or a value size".into()),
+                };
+                if value.is_empty() {
+                    return Err("An empty value is not a write".into());
+                }
+                Event::Write(key, value)
+            }
+            "delete" | "del" | "d" => Event::Delete(key),
+            op => return Err(format!("Unknown operation {}", op)),
+        })
+    }
+
+    // A key with the `0x` prefix is decoded from hex, otherwise its UTF-8 bytes are the key.
+    fn key(&self, key: &str) -> Result<Vec<u8>, String> {
+        let key = if key.starts_with("0x") {
+            decode_hex(key)?
+        } else {
+            key.as_bytes().to_vec()
+        };
+        Ok(match self.opts.key_hash {
+            KeyHash::Keccak => keccak_hash::keccak(&key).0.to_vec(),
+            KeyHash::None => key,
+        })
+    }
+
+    fn random_value(&mut self, size: usize) -> Vec<u8> {
+        (0..size).map(|_| self.random.gen::<u8>()).collect()
+    }
+}
+
+fn detect_format(input: &str) -> LogFormat {
+    let extension = Path::new(input)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("csv") => LogFormat::Csv,
+        Some("jsonl") | Some("json") => LogFormat::JsonLines,
+        _ => panic!(
+            "Cannot detect the format of {}, set it with --format",
+            input
+        ),
+    }
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, String> {
+    let digits = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+    if digits.len() % 2 != 0 {
+        return Err(format!("Odd number of hex digits in {}", s));
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("Invalid hex string {}", s))
+        })
+        .collect()
+}
+
+fn main() {
+    let opts = ImportOptions::from_args();
+    if opts.blocks_per_epoch == 0 {
+        panic!("The number of blocks per epoch must be positive")
+    }
+
+    let output = Path::new(&opts.output);
+    fs::create_dir_all(output).unwrap();
+
+    let mut importer = Importer {
+        opts: &opts,
+        random: Pcg64::seed_from_u64(opts.seed),
+    };
+    match opts.warmup {
+        Some(ref warmup) => importer.import(warmup, &output.join(WARMUP_FILE)),
+        None => TraceWriter::create(&output.join(WARMUP_FILE))
+            .unwrap()
+            .finish()
+            .unwrap(),
+    }
+    importer.import(&opts.input, &output.join(TASKS_FILE));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use asb_tasks::trace_file::TraceReader;
+    use std::path::PathBuf;
+
+    fn options(args: &[&str]) -> ImportOptions {
+        let base = ["asb-import", "input.jsonl", "output", "--key-hash", "none"];
+        ImportOptions::from_iter(base.iter().chain(args.iter()))
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("asb-import-{}-{}", std::process::id(), name))
+    }
+
+    // Import the JSON-lines `log` and read back the epochs.
+    fn import(name: &str, log: &str, args: &[&str]) -> Vec<Events> {
+        let (input, output) = (temp_path(&format!("{}.jsonl", name)), temp_path(name));
+        fs::write(&input, log).unwrap();
+        let opts = options(args);
+        let mut importer = Importer {
+            opts: &opts,
+            random: Pcg64::seed_from_u64(opts.seed),
+        };
+        importer.import(input.to_str().unwrap(), &output);
+        let epochs = TraceReader::open(&output).unwrap().collect();
+        fs::remove_file(&input).unwrap();
+        fs::remove_file(&output).unwrap();
+        epochs
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex("0x00ff1A"), Ok(vec![0x00, 0xff, 0x1a]));
+        assert_eq!(decode_hex("0a0b"), Ok(vec![0x0a, 0x0b]));
+        assert_eq!(decode_hex("0x"), Ok(vec![]));
+        assert!(decode_hex("0x123").is_err());
+        assert!(decode_hex("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_keys_and_values() {
+        let opts = options(&[]);
+        let mut importer = Importer {
+            opts: &opts,
+            random: Pcg64::seed_from_u64(opts.seed),
+        };
+        let record = |op: &str, key: &str, value: Option<LogValue>| LogRecord {
+            op: op.into(),
+            key: key.into(),
+            value,
+            block: 0,
+        };
+
+        // A key is decoded from hex only with the `0x` prefix.
+        assert_eq!(
+            importer.event(record("read", "0x0102", None)),
+            Ok(Event::Read(vec![1, 2]))
+        );
+        assert_eq!(
+            importer.event(record("r", "0102", None)),
+            Ok(Event::Read(b"0102".to_vec()))
+        );
+
+        // A value is a hex string or a size, either as a number or a string.
+        assert_eq!(
+            importer.event(record("put", "k", Some(LogValue::Text("0xabcd".into())))),
+            Ok(Event::Write(b"k".to_vec(), vec![0xab, 0xcd]))
+        );
+        for value in [LogValue::Size(40), LogValue::Text("40".into())] {
+            match importer.event(record("write", "k", Some(value))) {
+                Ok(Event::Write(_, value)) => assert_eq!(value.len(), 40),
+                _ => panic!("A size should generate a value"),
+            }
+        }
+        assert!(importer.event(record("write", "k", None)).is_err());
+        assert!(importer
+            .event(record("write", "k", Some(LogValue::Size(0))))
+            .is_err());
+        assert!(importer.event(record("scan", "k", None)).is_err());
+
+        // The keys are hashed by default.
+        let opts = ImportOptions::from_iter(["asb-import", "input.jsonl", "output"].iter());
+        let importer = Importer {
+            opts: &opts,
+            random: Pcg64::seed_from_u64(opts.seed),
+        };
+        assert_eq!(importer.key("k"), Ok(keccak_hash::keccak(b"k").0.to_vec()));
+    }
+
+    #[test]
+    fn test_blocks_per_epoch() {
+        let log = r#"
+{"op": "write", "key": "a", "value": "0x01", "block": 0}
+{"op": "read", "key": "a", "block": 1}
+{"op": "delete", "key": "a", "block": 2}
+{"op": "read", "key": "b", "block": 5}
+"#;
+        let read = |key: &str| Event::Read(key.as_bytes().to_vec());
+        let write = Event::Write(b"a".to_vec(), vec![1]);
+        let delete = Event::Delete(b"a".to_vec());
+
+        // The blocks without records do not become empty epochs.
+        assert_eq!(
+            import("one-block", log, &[]),
+            vec![
+                Events(vec![write]),
+                Events(vec![read("a")]),
+                Events(vec![delete]),
+                Events(vec![read("b")]),
+            ]
+        );
+
+        // The blocks 0-1, 2-3 and 4-5.
+        let write = Event::Write(b"a".to_vec(), vec![1]);
+        let delete = Event::Delete(b"a".to_vec());
+        assert_eq!(
+            import("two-blocks", log, &["--blocks-per-epoch", "2"]),
+            vec![
+                Events(vec![write, read("a")]),
+                Events(vec![delete]),
+                Events(vec![read("b")]),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "out of order")]
+    fn test_out_of_order_blocks() {
+        let log = r#"
+{"op": "read", "key": "a", "block": 2}
+{"op": "read", "key": "a", "block": 1}
+"#;
+        import("out-of-order", log, &[]);
+    }
+}
@@ -0,0 +1,201 @@
+use super::*;
+use asb_options::{Blockchain, Distribution, Options};
+use rand::prelude::*;
+use std::{
+    marker::PhantomData,
+    sync::{
+        mpsc::{sync_channel, Receiver},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Blocks of transactions on accounts and contract storage. Each epoch is a block, and a
+/// transaction either transfers between two accounts, or calls a contract which reads and writes
+/// some of its storage slots. The accounts, the contracts and the slots are drawn by the key
+/// distribution.
+///
+/// An account key is the 20-byte address followed by zeros, and a slot key is the address of the
+/// contract followed by 12 bytes of the hashed slot, so the slots of a contract share a prefix.
+pub struct BlockTransactions<R: Rng + SeedableRng> {
+    pub chain: Blockchain,
+    pub warmup_size: usize,
+    pub seed: u64,
+    pub read_batch: Option<usize>,
+    pub distribution: Distribution,
+    pub values: Arc<ValueSampler>,
+    _phantom: PhantomData<R>,
+}
+
+impl<R: Rng + SeedableRng> Clone for BlockTransactions<R> {
+    fn clone(&self) -> Self {
+        Self {
+            chain: self.chain.clone(),
+            warmup_size: self.warmup_size,
+            seed: self.seed,
+            read_batch: self.read_batch,
+            distribution: self.distribution.clone(),
+            values: self.values.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<R: Rng + SeedableRng> BlockTransactions<R> {
+    pub fn new(opts: &Options) -> Self {
+        Self {
+            chain: opts.blockchain.clone().unwrap(),
+            warmup_size: opts.epoch_size,
+            seed: opts.seed,
+            read_batch: opts.read_batch,
+            distribution: opts.distribution.clone(),
+            values: Arc::new(ValueSampler::new(opts)),
+            _phantom: PhantomData,
+        }
+    }
+
+    // The contracts are numbered after the accounts.
+    fn contract(&self, index: usize) -> usize {
+        self.chain.accounts + index
+    }
+}
+
+fn address(index: usize) -> [u8; 20] {
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash(&index.to_be_bytes())[..20]);
+    address
+}
+
+fn account_key(index: usize) -> Key {
+    let mut key = address(index).to_vec();
+    key.resize(32, 0);
+    key
+}
+
+fn slot_key(index: usize, slot: usize) -> Key {
+    let mut key = address(index).to_vec();
+    key.extend_from_slice(&hash(&slot.to_be_bytes())[..12]);
+    key
+}
+
+pub struct BlockTransactionsTaskGenerator {
+    receiver: Receiver<Events>,
+}
+
+impl BlockTransactionsTaskGenerator {
+    fn new<R: Rng + SeedableRng>(params: BlockTransactions<R>) -> Self {
+        let (sender, receiver) = sync_channel(10);
+
+        std::thread::spawn(move || {
+            let mut random = R::seed_from_u64(params.seed + 1);
+            let mut sampler = key_sampler(&params.distribution);
+            let chain = &params.chain;
+            loop {
+                let mut events = Vec::new();
+                for _ in 0..chain.txs {
+                    // The sender pays the fee and bumps its nonce in every transaction.
+                    let from = account_key(sampler.sample(&mut random, chain.accounts));
+                    let mut reads = vec![from.clone()];
+                    let mut writes = vec![from];
+                    if random.gen::<f64>() < chain.calls {
+                        let contract =
+                            params.contract(sampler.sample(&mut random, chain.contracts));
+                        reads.push(account_key(contract));
+                        for i in 0..chain.slot_reads {
+                            let slot = sampler.sample(&mut random, chain.slots);
+                            let key = slot_key(contract, slot);
+                            if i < chain.slot_writes {
+                                writes.push(key.clone());
+                            }
+                            reads.push(key);
+                        }
+                    } else {
+                        let to = loop {
+                            let to = account_key(sampler.sample(&mut random, chain.accounts));
+                            if to != reads[0] {
+                                break to;
+                            }
+                        };
+                        reads.push(to.clone());
+                        writes.push(to);
+                    }
+                    events.extend(reads.into_iter().map(Event::Read));
+                    for key in writes {
+                        events.push(Event::Write(key, params.values.value(&mut random)));
+                    }
+                }
+                if let Some(read_batch) = params.read_batch {
+                    events = batch_reads(events, read_batch);
+                }
+                let res = sender.send(Events(events));
+                if res.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+}
+
+impl Iterator for BlockTransactionsTaskGenerator {
+    type Item = Events;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let task = self.receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        Some(task)
+    }
+}
+
+pub struct BlockTransactionsWarmupIter<'a, R: Rng + SeedableRng> {
+    inner: &'a BlockTransactions<R>,
+    random: R,
+    // The account index, and the slot index plus one for a slot or zero for the account.
+    keys: Vec<(usize, usize)>,
+}
+
+impl<R: Rng + SeedableRng> Iterator for BlockTransactionsWarmupIter<'_, R> {
+    type Item = Events;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.keys.len().saturating_sub(self.inner.warmup_size);
+        let task_keys = self.keys.split_off(start);
+        if task_keys.is_empty() {
+            return None;
+        }
+        let mut events = Vec::with_capacity(task_keys.len());
+        for (index, slot) in task_keys.into_iter().rev() {
+            let key = match slot {
+                0 => account_key(index),
+                slot => slot_key(index, slot - 1),
+            };
+            events.push(Event::Write(key, self.inner.values.value(&mut self.random)));
+        }
+        Some(Events(events))
+    }
+}
+
+impl<R: Rng + SeedableRng> TaskTrait for BlockTransactions<R> {
+    fn warmup<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a> {
+        let mut random = R::seed_from_u64(self.seed + 1);
+        let mut keys = Vec::with_capacity(self.chain.total_keys());
+        keys.extend((0..self.chain.accounts).map(|index| (index, 0)));
+        for contract in (0..self.chain.contracts).map(|index| self.contract(index)) {
+            keys.extend((0..=self.chain.slots).map(|slot| (contract, slot)));
+        }
+        keys.shuffle(&mut random);
+        Box::new(BlockTransactionsWarmupIter {
+            inner: self,
+            random,
+            keys,
+        })
+    }
+
+    fn tasks(&self) -> Box<dyn Iterator<Item = Events>> {
+        Box::new(BlockTransactionsTaskGenerator::new(self.clone()))
+    }
+
+    fn num_keys(&self, _epochs: usize) -> Option<usize> {
+        Some(self.chain.total_keys())
+    }
+}
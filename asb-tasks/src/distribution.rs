@@ -0,0 +1,191 @@
+use asb_options::Distribution;
+use rand::prelude::*;
+
+/// Picks the index of the accessed key among the keys of a random task.
+pub trait KeySampler: Send {
+    fn sample(&mut self, random: &mut dyn RngCore, num_keys: usize) -> usize;
+}
+
+pub fn key_sampler(distribution: &Distribution) -> Box<dyn KeySampler> {
+    match *distribution {
+        Distribution::Uniform => Box::new(Uniform),
+        Distribution::Zipf(skew) => Box::new(Zipf::new(skew)),
+        Distribution::Hotspot { ops, keys } => Box::new(Hotspot { ops, keys }),
+        Distribution::Latest(skew) => Box::new(Latest(Zipf::new(skew))),
+    }
+}
+
+pub struct Uniform;
+
+impl KeySampler for Uniform {
+    fn sample(&mut self, random: &mut dyn RngCore, num_keys: usize) -> usize {
+        random.gen_range(0, num_keys)
+    }
+}
+
+/// Samples the rank of a Zipfian distribution by the rejection-inversion method of Hörmann and
+/// Derflinger, which takes constant time for any number of keys. The rank 0 is the most popular.
+pub struct Zipf {
+    skew: f64,
+    num_keys: usize,
+    h_integral_x1: f64,
+    h_integral_n: f64,
+    s: f64,
+}
+
+impl Zipf {
+    pub fn new(skew: f64) -> Self {
+        Self {
+            skew,
+            num_keys: 0,
+            h_integral_x1: 0.0,
+            h_integral_n: 0.0,
+            s: 0.0,
+        }
+    }
+
+    fn resize(&mut self, num_keys: usize) {
+        self.num_keys = num_keys;
+        self.h_integral_x1 = self.h_integral(1.5) - 1.0;
+        self.h_integral_n = self.h_integral(num_keys as f64 + 0.5);
+        self.s = 2.0 - self.h_integral_inverse(self.h_integral(2.5) - self.h(2.0));
+    }
+
+    fn h(&self, x: f64) -> f64 {
+        (-self.skew * x.ln()).exp()
+    }
+
+    fn h_integral(&self, x: f64) -> f64 {
+        let log_x = x.ln();
+        helper2((1.0 - self.skew) * log_x) * log_x
+    }
+
+    fn h_integral_inverse(&self, x: f64) -> f64 {
+        let t = f64::max(x * (1.0 - self.skew), -1.0);
+        (helper1(t) * x).exp()
+    }
+}
+
+impl KeySampler for Zipf {
+    fn sample(&mut self, random: &mut dyn RngCore, num_keys: usize) -> usize {
+        if num_keys != self.num_keys {
+            self.resize(num_keys);
+        }
+        loop {
+            let u =
+                self.h_integral_n + random.gen::<f64>() * (self.h_integral_x1 - self.h_integral_n);
+            let x = self.h_integral_inverse(u);
+            let k = (x + 0.5).clamp(1.0, num_keys as f64).floor();
+            if k - x <= self.s || u >= self.h_integral(k + 0.5) - self.h(k) {
+                return k as usize - 1;
+            }
+        }
+    }
+}
+
+// `ln(1 + x) / x`, accurate for small `x`.
+fn helper1(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.ln_1p() / x
+    } else {
+        1.0 - x * (0.5 - x * (1.0 / 3.0 - 0.25 * x))
+    }
+}
+
+// `(exp(x) - 1) / x`, accurate for small `x`.
+fn helper2(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.exp_m1() / x
+    } else {
+        1.0 + x * 0.5 * (1.0 + x / 3.0 * (1.0 + 0.25 * x))
+    }
+}
+
+/// The fraction `ops` of samples are uniform over the first fraction `keys` of keys, the others
+/// are uniform over the remaining keys.
+pub struct Hotspot {
+    ops: f64,
+    keys: f64,
+}
+
+impl KeySampler for Hotspot {
+    fn sample(&mut self, random: &mut dyn RngCore, num_keys: usize) -> usize {
+        let hot_keys = ((num_keys as f64 * self.keys).ceil() as usize).clamp(1, num_keys);
+        if hot_keys == num_keys || random.gen_bool(self.ops) {
+            random.gen_range(0, hot_keys)
+        } else {
+            random.gen_range(hot_keys, num_keys)
+        }
+    }
+}
+
+/// Zipfian over the keys from the latest inserted one, assuming the key `i` is the `i`-th
+/// inserted.
+pub struct Latest(Zipf);
+
+impl KeySampler for Latest {
+    fn sample(&mut self, random: &mut dyn RngCore, num_keys: usize) -> usize {
+        num_keys - 1 - self.0.sample(random, num_keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_pcg::Pcg64;
+
+    const SAMPLES: usize = 200_000;
+
+    fn histogram(sampler: &mut dyn KeySampler, num_keys: usize) -> Vec<usize> {
+        let mut random = Pcg64::seed_from_u64(64);
+        let mut counts = vec![0; num_keys];
+        for _ in 0..SAMPLES {
+            let index = sampler.sample(&mut random, num_keys);
+            assert!(index < num_keys);
+            counts[index] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_zipf() {
+        let counts = histogram(&mut Zipf::new(0.99), 1000);
+        // The frequency decreases with the rank.
+        for rank in 1..10 {
+            assert!(counts[rank - 1] > counts[rank], "Fail on rank {}", rank);
+        }
+        let ratio = counts[0] as f64 / counts[1] as f64;
+        assert!((ratio / 2f64.powf(0.99) - 1.0).abs() < 0.05);
+        // The top 10% keys take most of the accesses.
+        let top = counts[..100].iter().sum::<usize>() as f64 / SAMPLES as f64;
+        assert!(top > 0.6 && top < 0.8);
+
+        assert_eq!(histogram(&mut Zipf::new(0.99), 1), vec![SAMPLES]);
+    }
+
+    #[test]
+    fn test_hotspot() {
+        let mut hotspot = Hotspot {
+            ops: 0.8,
+            keys: 0.2,
+        };
+        let counts = histogram(&mut hotspot, 1000);
+        let hot = counts[..200].iter().sum::<usize>() as f64 / SAMPLES as f64;
+        assert!((hot - 0.8).abs() < 0.01);
+
+        assert_eq!(histogram(&mut hotspot, 1), vec![SAMPLES]);
+    }
+
+    #[test]
+    fn test_latest() {
+        let (mut latest, mut zipf) = (Latest(Zipf::new(0.99)), Zipf::new(0.99));
+        let (mut random1, mut random2) = (Pcg64::seed_from_u64(64), Pcg64::seed_from_u64(64));
+        for _ in 0..1000 {
+            let index = latest.sample(&mut random1, 1000);
+            assert_eq!(index, 999 - zipf.sample(&mut random2, 1000));
+        }
+        // The latest inserted key is the most popular.
+        let counts = histogram(&mut Latest(Zipf::new(0.99)), 1000);
+        assert!(counts[999] > counts[998] && counts[998] > counts[0]);
+    }
+}
@@ -0,0 +1,119 @@
+use super::*;
+use asb_options::Options;
+use rand::prelude::*;
+use std::{
+    sync::mpsc::{sync_channel, Receiver},
+    time::Duration,
+};
+
+/// Same as `ReadThenWrite`, except that the given fraction of operations write fresh keys instead,
+/// so the state grows by a fixed number of keys every epoch. The fresh keys are numbered after the
+/// initial keys, and can be accessed by the following operations.
+pub struct GrowingState<R: Rng + SeedableRng> {
+    pub inner: ReadThenWrite<R>,
+    pub grow_ratio: f64,
+}
+
+impl<R: Rng + SeedableRng> Clone for GrowingState<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            grow_ratio: self.grow_ratio,
+        }
+    }
+}
+
+impl<R: Rng + SeedableRng> GrowingState<R> {
+    pub fn new(opts: &Options) -> Self {
+        Self {
+            inner: ReadThenWrite::new(opts),
+            grow_ratio: opts.grow_ratio.unwrap(),
+        }
+    }
+
+    // The operation `index` of an epoch writes a fresh key, with the fresh keys evenly spaced.
+    fn is_insert(&self, index: usize) -> bool {
+        let inserts = |ops: usize| (ops as f64 * self.grow_ratio) as usize;
+        inserts(index + 1) > inserts(index)
+    }
+
+    fn inserts_per_epoch(&self) -> usize {
+        (self.inner.batch_size as f64 * self.grow_ratio) as usize
+    }
+}
+
+pub struct GrowingStateTaskGenerator {
+    receiver: Receiver<Events>,
+}
+
+impl GrowingStateTaskGenerator {
+    fn new<R: Rng + SeedableRng>(params: GrowingState<R>) -> Self {
+        let (sender, receiver) = sync_channel(10);
+
+        std::thread::spawn(move || {
+            let mut random = R::seed_from_u64(params.inner.seed + 1);
+            let mut sampler = key_sampler(&params.inner.distribution);
+            let mut num_keys = params.inner.total_keys;
+            loop {
+                // Each operation reads the key first unless the key is fresh.
+                let mut ops = Vec::with_capacity(params.inner.batch_size);
+                for index in 0..params.inner.batch_size {
+                    let value = params.inner.values.value(&mut random);
+                    if params.is_insert(index) {
+                        ops.push((false, hash(&num_keys.to_be_bytes()).to_vec(), value));
+                        num_keys += 1;
+                    } else {
+                        let integer = sampler.sample(&mut random, num_keys);
+                        ops.push((true, hash(&integer.to_be_bytes()).to_vec(), value));
+                    }
+                }
+
+                // As `ReadThenWrite`, the batched reads are issued before the writes.
+                let mut events = Vec::with_capacity(params.inner.batch_size * 2);
+                for chunk in ops.chunks(params.inner.read_batch.unwrap_or(1)) {
+                    let reads = chunk.iter().filter(|op| op.0).map(|op| op.1.clone());
+                    if params.inner.read_batch.is_some() {
+                        let keys: Vec<_> = reads.collect();
+                        if !keys.is_empty() {
+                            events.push(Event::ReadMany(keys));
+                        }
+                    } else {
+                        events.extend(reads.map(Event::Read));
+                    }
+                    for (_, key, value) in chunk {
+                        events.push(Event::Write(key.clone(), value.clone()));
+                    }
+                }
+                let res = sender.send(Events(events));
+                if res.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+}
+
+impl Iterator for GrowingStateTaskGenerator {
+    type Item = Events;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let task = self.receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        Some(task)
+    }
+}
+
+impl<R: Rng + SeedableRng> TaskTrait for GrowingState<R> {
+    fn warmup<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a> {
+        self.inner.warmup()
+    }
+
+    fn tasks(&self) -> Box<dyn Iterator<Item = Events>> {
+        Box::new(GrowingStateTaskGenerator::new(self.clone()))
+    }
+
+    fn num_keys(&self, epochs: usize) -> Option<usize> {
+        Some(self.inner.total_keys + epochs * self.inserts_per_epoch())
+    }
+}
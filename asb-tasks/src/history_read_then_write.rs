@@ -0,0 +1,89 @@
+use super::*;
+use asb_options::Options;
+use rand::prelude::*;
+use std::{
+    sync::mpsc::{sync_channel, Receiver},
+    time::Duration,
+};
+
+/// Same as `ReadThenWrite`, except that the reads access the states of the last few epochs.
+pub struct HistoryReadThenWrite<R: Rng + SeedableRng> {
+    pub inner: ReadThenWrite<R>,
+    pub history_epochs: usize,
+}
+
+impl<R: Rng + SeedableRng> Clone for HistoryReadThenWrite<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            history_epochs: self.history_epochs,
+        }
+    }
+}
+
+impl<R: Rng + SeedableRng> HistoryReadThenWrite<R> {
+    pub fn new(opts: &Options) -> Self {
+        Self {
+            inner: ReadThenWrite::new(opts),
+            history_epochs: opts.history_epochs.unwrap(),
+        }
+    }
+}
+
+pub struct HistoryReadThenWriteTaskGenerator {
+    receiver: Receiver<Events>,
+}
+
+impl HistoryReadThenWriteTaskGenerator {
+    fn new<R: Rng + SeedableRng>(params: HistoryReadThenWrite<R>) -> Self {
+        let (sender, receiver) = sync_channel(10);
+
+        std::thread::spawn(move || {
+            let mut random = R::seed_from_u64(params.inner.seed + 1);
+            let mut sampler = key_sampler(&params.inner.distribution);
+            for epoch in 0usize.. {
+                let history_epochs = usize::min(params.history_epochs, epoch);
+                let mut events = Vec::with_capacity(params.inner.batch_size * 2);
+                for _ in 0..params.inner.batch_size {
+                    let integer = sampler.sample(&mut random, params.inner.total_keys);
+                    let key = hash(&integer.to_be_bytes()).to_vec();
+                    if history_epochs > 0 {
+                        let read_epoch = epoch - random.gen_range(1, history_epochs + 1);
+                        events.push(Event::ReadAt(read_epoch, key.clone()));
+                    } else {
+                        events.push(Event::Read(key.clone()));
+                    }
+                    events.push(Event::Write(
+                        key.clone(),
+                        params.inner.values.value(&mut random),
+                    ));
+                }
+                let res = sender.send(Events(events));
+                if res.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+}
+
+impl Iterator for HistoryReadThenWriteTaskGenerator {
+    type Item = Events;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let task = self.receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        Some(task)
+    }
+}
+
+impl<R: Rng + SeedableRng> TaskTrait for HistoryReadThenWrite<R> {
+    fn warmup<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a> {
+        self.inner.warmup()
+    }
+
+    fn tasks(&self) -> Box<dyn Iterator<Item = Events>> {
+        Box::new(HistoryReadThenWriteTaskGenerator::new(self.clone()))
+    }
+}
@@ -1,11 +1,28 @@
+pub mod block_transactions;
+pub mod distribution;
+pub mod growing_state;
+pub mod history_read_then_write;
+pub mod mixed_read_write;
+pub mod read_prove_then_write;
 pub mod read_then_write;
 pub mod real_trace;
+pub mod recorded_trace;
+pub mod trace_file;
+pub mod value;
 
 use asb_options::Options;
 use std::sync::Arc;
 
+pub use block_transactions::BlockTransactions;
+pub use distribution::{key_sampler, KeySampler};
+pub use growing_state::GrowingState;
+pub use history_read_then_write::HistoryReadThenWrite;
+pub use mixed_read_write::MixedReadWrite;
+pub use read_prove_then_write::ReadProveThenWrite;
 pub use read_then_write::ReadThenWrite;
 pub use real_trace::RealTrace;
+pub use recorded_trace::{record_trace, RecordedTrace};
+pub use value::ValueSampler;
 
 type Key = Vec<u8>;
 type Value = Vec<u8>;
@@ -13,25 +30,90 @@ type Value = Vec<u8>;
 pub fn tasks(opts: &Options) -> Arc<dyn TaskTrait> {
     if opts.real_trace {
         Arc::new(RealTrace::new(&opts, opts.warmup_from.is_none()))
+    } else if opts.replay_trace.is_some() {
+        Arc::new(RecordedTrace::new(&opts))
+    } else if opts.blockchain.is_some() {
+        Arc::new(BlockTransactions::<rand_pcg::Pcg64>::new(&opts))
+    } else if opts.mix.is_some() {
+        Arc::new(MixedReadWrite::<rand_pcg::Pcg64>::new(&opts))
+    } else if opts.grow_ratio.is_some() {
+        Arc::new(GrowingState::<rand_pcg::Pcg64>::new(&opts))
+    } else if opts.history_epochs.is_some() {
+        Arc::new(HistoryReadThenWrite::<rand_pcg::Pcg64>::new(&opts))
+    } else if opts.prove_ratio.is_some() {
+        Arc::new(ReadProveThenWrite::<rand_pcg::Pcg64>::new(&opts))
     } else {
         Arc::new(ReadThenWrite::<rand_pcg::Pcg64>::new(&opts))
     }
 }
 
+/// The keys read by the concurrent reader thread `thread`, drawn from the keys of the random tasks
+/// by the key distribution.
+pub fn reader_keys(opts: &Options, thread: usize) -> Box<dyn Iterator<Item = Key> + Send> {
+    use rand::prelude::*;
+    let total_keys = opts.total_keys;
+    let mut random = rand_pcg::Pcg64::seed_from_u64(opts.seed + 1000 + thread as u64);
+    let mut sampler = key_sampler(&opts.distribution);
+    Box::new(std::iter::repeat_with(move || {
+        let integer = sampler.sample(&mut random, total_keys);
+        hash(&integer.to_be_bytes()).to_vec()
+    }))
+}
+
 pub trait TaskTrait {
     fn warmup<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a> {
         Box::new(NoopIter)
     }
     fn tasks<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a>;
+    /// The number of keys written by the warmup and the given number of epochs, if the task
+    /// tracks the growth of the state.
+    fn num_keys(&self, _epochs: usize) -> Option<usize> {
+        None
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
 pub enum Event {
     Read(Key),
+    /// Read a batch of keys at once.
+    ReadMany(Vec<Key>),
     Write(Key, Value),
+    Delete(Key),
+    Prove(Key),
+    /// Read a key in the state committed at the given epoch.
+    ReadAt(usize, Key),
 }
 
+#[derive(Debug, PartialEq, Eq)]
 pub struct Events(pub Vec<Event>);
 
+// Merge the runs of consecutive reads into batches of at most `batch_size` keys. The order of reads
+// and writes is kept.
+fn batch_reads(events: Vec<Event>, batch_size: usize) -> Vec<Event> {
+    let mut answer = Vec::with_capacity(events.len());
+    let mut keys = Vec::new();
+    for event in events {
+        match event {
+            Event::Read(key) => {
+                keys.push(key);
+                if keys.len() == batch_size {
+                    answer.push(Event::ReadMany(std::mem::take(&mut keys)));
+                }
+            }
+            other => {
+                if !keys.is_empty() {
+                    answer.push(Event::ReadMany(std::mem::take(&mut keys)));
+                }
+                answer.push(other);
+            }
+        }
+    }
+    if !keys.is_empty() {
+        answer.push(Event::ReadMany(keys));
+    }
+    answer
+}
+
 fn hash(input: &[u8]) -> [u8; 32] {
     keccak_hash::keccak(input).0
 }
@@ -0,0 +1,101 @@
+use super::*;
+use asb_options::{Mix, Options};
+use rand::prelude::*;
+use std::{
+    sync::mpsc::{sync_channel, Receiver},
+    time::Duration,
+};
+
+/// A YCSB-style task with a configurable mix of reads, read-then-writes, inserts and blind writes.
+/// It is warmed up as `ReadThenWrite`, the inserted keys are numbered after the initial keys.
+pub struct MixedReadWrite<R: Rng + SeedableRng> {
+    pub inner: ReadThenWrite<R>,
+    pub mix: Mix,
+}
+
+impl<R: Rng + SeedableRng> Clone for MixedReadWrite<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            mix: self.mix.clone(),
+        }
+    }
+}
+
+impl<R: Rng + SeedableRng> MixedReadWrite<R> {
+    pub fn new(opts: &Options) -> Self {
+        Self {
+            inner: ReadThenWrite::new(opts),
+            mix: opts.mix.clone().unwrap(),
+        }
+    }
+}
+
+pub struct MixedReadWriteTaskGenerator {
+    receiver: Receiver<Events>,
+}
+
+impl MixedReadWriteTaskGenerator {
+    fn new<R: Rng + SeedableRng>(params: MixedReadWrite<R>) -> Self {
+        let (sender, receiver) = sync_channel(10);
+
+        std::thread::spawn(move || {
+            let mut random = R::seed_from_u64(params.inner.seed + 1);
+            let mut sampler = key_sampler(&params.inner.distribution);
+            let mix = &params.mix;
+            // The first keys are read-only, and the writes only access the others.
+            let read_only_keys = (params.inner.total_keys as f64 * mix.read_only_keys) as usize;
+            let mut num_keys = params.inner.total_keys;
+            loop {
+                let mut events = Vec::with_capacity(params.inner.batch_size * 2);
+                for _ in 0..params.inner.batch_size {
+                    let x = random.gen::<f64>();
+                    if x < mix.read {
+                        let integer = sampler.sample(&mut random, num_keys);
+                        events.push(Event::Read(hash(&integer.to_be_bytes()).to_vec()));
+                    } else if x < mix.read + mix.insert {
+                        let key = hash(&num_keys.to_be_bytes()).to_vec();
+                        num_keys += 1;
+                        events.push(Event::Write(key, params.inner.values.value(&mut random)));
+                    } else {
+                        let integer =
+                            read_only_keys + sampler.sample(&mut random, num_keys - read_only_keys);
+                        let key = hash(&integer.to_be_bytes()).to_vec();
+                        if x < mix.read + mix.insert + mix.write {
+                            events.push(Event::Read(key.clone()));
+                        }
+                        events.push(Event::Write(key, params.inner.values.value(&mut random)));
+                    }
+                }
+                if let Some(read_batch) = params.inner.read_batch {
+                    events = batch_reads(events, read_batch);
+                }
+                let res = sender.send(Events(events));
+                if res.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+}
+
+impl Iterator for MixedReadWriteTaskGenerator {
+    type Item = Events;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let task = self.receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        Some(task)
+    }
+}
+
+impl<R: Rng + SeedableRng> TaskTrait for MixedReadWrite<R> {
+    fn warmup<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a> {
+        self.inner.warmup()
+    }
+
+    fn tasks(&self) -> Box<dyn Iterator<Item = Events>> {
+        Box::new(MixedReadWriteTaskGenerator::new(self.clone()))
+    }
+}
@@ -0,0 +1,87 @@
+use super::*;
+use asb_options::Options;
+use rand::prelude::*;
+use std::{
+    sync::mpsc::{sync_channel, Receiver},
+    time::Duration,
+};
+
+/// Same as `ReadThenWrite`, except that a fraction of the reads are replaced by proof requests.
+pub struct ReadProveThenWrite<R: Rng + SeedableRng> {
+    pub inner: ReadThenWrite<R>,
+    pub prove_ratio: f64,
+}
+
+impl<R: Rng + SeedableRng> Clone for ReadProveThenWrite<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            prove_ratio: self.prove_ratio,
+        }
+    }
+}
+
+impl<R: Rng + SeedableRng> ReadProveThenWrite<R> {
+    pub fn new(opts: &Options) -> Self {
+        Self {
+            inner: ReadThenWrite::new(opts),
+            prove_ratio: opts.prove_ratio.unwrap_or(0.0),
+        }
+    }
+}
+
+pub struct ReadProveThenWriteTaskGenerator {
+    receiver: Receiver<Events>,
+}
+
+impl ReadProveThenWriteTaskGenerator {
+    fn new<R: Rng + SeedableRng>(params: ReadProveThenWrite<R>) -> Self {
+        let (sender, receiver) = sync_channel(10);
+
+        std::thread::spawn(move || {
+            let mut random = R::seed_from_u64(params.inner.seed + 1);
+            let mut sampler = key_sampler(&params.inner.distribution);
+            loop {
+                let mut events = Vec::with_capacity(params.inner.batch_size * 2);
+                for _ in 0..params.inner.batch_size {
+                    let integer = sampler.sample(&mut random, params.inner.total_keys);
+                    let key = hash(&integer.to_be_bytes()).to_vec();
+                    if random.gen_bool(params.prove_ratio) {
+                        events.push(Event::Prove(key.clone()));
+                    } else {
+                        events.push(Event::Read(key.clone()));
+                    }
+                    events.push(Event::Write(
+                        key.clone(),
+                        params.inner.values.value(&mut random),
+                    ));
+                }
+                let res = sender.send(Events(events));
+                if res.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+}
+
+impl Iterator for ReadProveThenWriteTaskGenerator {
+    type Item = Events;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let task = self.receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        Some(task)
+    }
+}
+
+impl<R: Rng + SeedableRng> TaskTrait for ReadProveThenWrite<R> {
+    fn warmup<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a> {
+        self.inner.warmup()
+    }
+
+    fn tasks(&self) -> Box<dyn Iterator<Item = Events>> {
+        Box::new(ReadProveThenWriteTaskGenerator::new(self.clone()))
+    }
+}
@@ -1,9 +1,12 @@
 use super::*;
-use asb_options::Options;
+use asb_options::{Distribution, Options};
 use rand::prelude::*;
 use std::{
     marker::PhantomData,
-    sync::mpsc::{sync_channel, Receiver},
+    sync::{
+        mpsc::{sync_channel, Receiver},
+        Arc,
+    },
     time::Duration,
 };
 
@@ -11,6 +14,9 @@ pub struct ReadThenWrite<R: Rng + SeedableRng> {
     pub total_keys: usize,
     pub batch_size: usize,
     pub seed: u64,
+    pub read_batch: Option<usize>,
+    pub distribution: Distribution,
+    pub values: Arc<ValueSampler>,
     _phantom: PhantomData<R>,
 }
 
@@ -20,6 +26,9 @@ impl<R: Rng + SeedableRng> Clone for ReadThenWrite<R> {
             total_keys: self.total_keys.clone(),
             batch_size: self.batch_size.clone(),
             seed: self.seed.clone(),
+            read_batch: self.read_batch,
+            distribution: self.distribution.clone(),
+            values: self.values.clone(),
             _phantom: PhantomData,
         }
     }
@@ -31,6 +40,9 @@ impl<R: Rng + SeedableRng> ReadThenWrite<R> {
             total_keys: opts.total_keys,
             batch_size: opts.epoch_size,
             seed: opts.seed,
+            read_batch: opts.read_batch,
+            distribution: opts.distribution.clone(),
+            values: Arc::new(ValueSampler::new(opts)),
             _phantom: PhantomData,
         }
     }
@@ -46,13 +58,33 @@ impl ReadThenWriteTaskGenerator {
 
         std::thread::spawn(move || {
             let mut random = R::seed_from_u64(params.seed + 1);
+            let mut sampler = key_sampler(&params.distribution);
             loop {
                 let mut events = Vec::with_capacity(params.batch_size * 2);
-                for _ in 0..params.batch_size {
-                    let integer = random.gen_range(0, params.total_keys);
-                    let key = hash(&integer.to_be_bytes()).to_vec();
-                    events.push(Event::Read(key.clone()));
-                    events.push(Event::Write(key.clone(), random.gen::<[u8; 32]>().to_vec()));
+                if let Some(read_batch) = params.read_batch {
+                    // Read a batch of keys first, then write them back.
+                    let mut remaining = params.batch_size;
+                    while remaining > 0 {
+                        let size = std::cmp::min(read_batch, remaining);
+                        remaining -= size;
+                        let keys: Vec<_> = (0..size)
+                            .map(|_| {
+                                let integer = sampler.sample(&mut random, params.total_keys);
+                                hash(&integer.to_be_bytes()).to_vec()
+                            })
+                            .collect();
+                        events.push(Event::ReadMany(keys.clone()));
+                        for key in keys {
+                            events.push(Event::Write(key, params.values.value(&mut random)));
+                        }
+                    }
+                } else {
+                    for _ in 0..params.batch_size {
+                        let integer = sampler.sample(&mut random, params.total_keys);
+                        let key = hash(&integer.to_be_bytes()).to_vec();
+                        events.push(Event::Read(key.clone()));
+                        events.push(Event::Write(key.clone(), params.values.value(&mut random)));
+                    }
                 }
                 let res = sender.send(Events(events));
                 if res.is_err() {
@@ -100,7 +132,7 @@ impl<R: Rng + SeedableRng> Iterator for ReadThenWriteWarmupIter<'_, R> {
             let key = hash(&key.to_be_bytes()).to_vec();
             events.push(Event::Write(
                 key.clone(),
-                self.random.gen::<[u8; 32]>().to_vec(),
+                self.inner.values.value(&mut self.random),
             ));
         }
         Some(Events(events))
@@ -111,7 +143,12 @@ impl<R: Rng + SeedableRng> TaskTrait for ReadThenWrite<R> {
     fn warmup<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a> {
         let mut random = R::seed_from_u64(self.seed + 1);
         let mut keys: Vec<usize> = (0..self.total_keys).collect();
-        keys.shuffle(&mut random);
+        match self.distribution {
+            // The keys are popped from the back, so the key `i` is the `i`-th inserted, as the
+            // latest distribution assumes.
+            Distribution::Latest(_) => keys.reverse(),
+            _ => keys.shuffle(&mut random),
+        }
         Box::new(ReadThenWriteWarmupIter {
             inner: &self,
             random,
@@ -9,7 +9,7 @@ use std::sync::mpsc::{channel, Receiver, Sender};
 
 use asb_options::{AuthAlgo, Options};
 
-use super::{Event, Events, TaskTrait};
+use super::{batch_reads, Event, Events, TaskTrait};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 enum ExperimentTask {
@@ -21,6 +21,15 @@ type Bytes = Vec<u8>;
 type InitTasks = Vec<([u8; 32], Bytes)>;
 type BlockTask = VecDeque<ExperimentTask>;
 
+// Ethereum clears a storage slot by writing zero to it, so a zero (or empty) value is a deletion.
+fn write_event(key: &[u8; 32], value: &Bytes) -> Event {
+    if value.iter().all(|x| *x == 0) {
+        Event::Delete(key.to_vec())
+    } else {
+        Event::Write(key.to_vec(), value.clone())
+    }
+}
+
 fn read_from_file<T, S: AsRef<Path>>(path: S) -> T
 where
     for<'a> T: Deserialize<'a>,
@@ -29,14 +38,27 @@ where
     from_bytes(&loaded).unwrap()
 }
 
+/// The histogram of the value sizes in the initial state of the trace, skipping the deletions.
+pub(crate) fn init_value_sizes(trace_dir: &str) -> Vec<(usize, u64)> {
+    let init_tasks: InitTasks = read_from_file(Path::new(trace_dir).join("real_trace.init"));
+    let mut histogram = std::collections::BTreeMap::new();
+    for (_, value) in init_tasks.iter() {
+        if value.iter().any(|x| *x != 0) {
+            *histogram.entry(value.len()).or_insert(0u64) += 1;
+        }
+    }
+    histogram.into_iter().collect()
+}
+
 pub struct TaskProducer {
     receiver: Receiver<Vec<Events>>,
     group_size: usize,
+    read_batch: Option<usize>,
     events: Vec<Events>,
 }
 
 impl TaskProducer {
-    fn new(path: String, group_size: usize) -> Self {
+    fn new(path: String, group_size: usize, read_batch: Option<usize>) -> Self {
         let (sender, receiver) = sync_channel(1);
         std::thread::spawn(move || {
             let path = Path::new(&path);
@@ -53,9 +75,7 @@ impl TaskProducer {
                                 .into_iter()
                                 .map(|io| match io {
                                     ExperimentTask::Read(key) => Event::Read(key.to_vec()),
-                                    ExperimentTask::Write(key, value) => {
-                                        Event::Write(key.to_vec(), value.clone())
-                                    }
+                                    ExperimentTask::Write(key, value) => write_event(&key, &value),
                                 })
                                 .collect::<Vec<_>>(),
                         )
@@ -71,6 +91,7 @@ impl TaskProducer {
             receiver,
             events: vec![],
             group_size,
+            read_batch,
         }
     }
 
@@ -94,7 +115,7 @@ impl TaskProducer {
 impl Iterator for TaskProducer {
     type Item = Events;
     fn next(&mut self) -> Option<Events> {
-        if self.group_size == 1 {
+        let events = if self.group_size == 1 {
             self.pick_next()
         } else {
             let mut grouped_events = Vec::with_capacity(100_000);
@@ -108,6 +129,10 @@ impl Iterator for TaskProducer {
             } else {
                 Some(Events(grouped_events))
             }
+        };
+        match self.read_batch {
+            Some(batch_size) => events.map(|events| Events(batch_reads(events.0, batch_size))),
+            None => events,
         }
     }
 }
@@ -116,6 +141,7 @@ pub struct RealTrace {
     path: String,
     init_tasks: Option<InitTasks>,
     group_size: usize,
+    read_batch: Option<usize>,
 }
 
 impl RealTrace {
@@ -129,25 +155,30 @@ impl RealTrace {
             } else {
                 None
             },
-            group_size: if opt.algorithm == AuthAlgo::RAIN || opt.algorithm == AuthAlgo::MPT {
+            group_size: if matches!(opt.algorithm, AuthAlgo::RAIN | AuthAlgo::MPT(..)) {
                 50
             } else {
                 1
             },
+            read_batch: opt.read_batch,
         }
     }
 }
 
 impl TaskTrait for RealTrace {
     fn tasks(&self) -> Box<dyn Iterator<Item = Events>> {
-        Box::new(TaskProducer::new(self.path.clone(), self.group_size))
+        Box::new(TaskProducer::new(
+            self.path.clone(),
+            self.group_size,
+            self.read_batch,
+        ))
     }
 
     fn warmup<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a> {
         Box::new(self.init_tasks.as_ref().unwrap().chunks(1000).map(|arr| {
             Events(
                 arr.iter()
-                    .map(|(key, value)| Event::Write(key.to_vec(), value.clone()))
+                    .map(|(key, value)| write_event(key, value))
                     .collect::<Vec<_>>(),
             )
         }))
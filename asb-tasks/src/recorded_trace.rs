@@ -0,0 +1,107 @@
+use super::trace_file::{TraceReader, TraceWriter, TASKS_FILE, WARMUP_FILE};
+use super::*;
+use asb_options::Options;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{sync_channel, Receiver},
+    time::Duration,
+};
+
+/// Record the warmup and the first `epochs` epochs of `tasks` into the trace directory `dir`.
+pub fn record_trace(tasks: &dyn TaskTrait, dir: &str, epochs: usize, warmup: bool) {
+    let dir = Path::new(dir);
+    fs::create_dir_all(dir).unwrap();
+
+    let mut writer = TraceWriter::create(&dir.join(WARMUP_FILE)).unwrap();
+    if warmup {
+        for (epoch, events) in tasks.warmup().enumerate() {
+            writer.write_epoch(&events).unwrap();
+            if (epoch + 1) % 100 == 0 {
+                println!("Recorded {} warmup epochs", epoch + 1);
+            }
+        }
+    }
+    writer.finish().unwrap();
+
+    let mut writer = TraceWriter::create(&dir.join(TASKS_FILE)).unwrap();
+    for (epoch, events) in tasks.tasks().take(epochs).enumerate() {
+        writer.write_epoch(&events).unwrap();
+        if (epoch + 1) % 100 == 0 {
+            println!("Recorded {} epochs", epoch + 1);
+        }
+    }
+    writer.finish().unwrap();
+}
+
+/// Replays a trace directory written by `record_trace`.
+pub struct RecordedTrace {
+    dir: PathBuf,
+    read_batch: Option<usize>,
+}
+
+impl RecordedTrace {
+    pub fn new(opts: &Options) -> Self {
+        let dir = PathBuf::from(opts.replay_trace.as_ref().unwrap());
+        for file in [WARMUP_FILE, TASKS_FILE] {
+            // Check the header early.
+            if let Err(e) = TraceReader::open(&dir.join(file)) {
+                panic!("Cannot open {} in {}: {}", file, dir.display(), e);
+            }
+        }
+        Self {
+            dir,
+            read_batch: opts.read_batch,
+        }
+    }
+}
+
+pub struct RecordedTraceTaskGenerator {
+    receiver: Receiver<Events>,
+}
+
+impl RecordedTraceTaskGenerator {
+    fn new(path: PathBuf, read_batch: Option<usize>) -> Self {
+        let (sender, receiver) = sync_channel(10);
+
+        std::thread::spawn(move || {
+            for events in TraceReader::open(&path).unwrap() {
+                let events = match read_batch {
+                    Some(batch_size) => Events(batch_reads(events.0, batch_size)),
+                    None => events,
+                };
+                let res = sender.send(events);
+                if res.is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+}
+
+impl Iterator for RecordedTraceTaskGenerator {
+    type Item = Events;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.receiver.recv_timeout(Duration::from_secs(1)) {
+            Ok(events) => Some(events),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => panic!("Load trace timeout"),
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+impl TaskTrait for RecordedTrace {
+    fn warmup<'a>(&'a self) -> Box<dyn Iterator<Item = Events> + 'a> {
+        Box::new(TraceReader::open(&self.dir.join(WARMUP_FILE)).unwrap())
+    }
+
+    fn tasks(&self) -> Box<dyn Iterator<Item = Events>> {
+        Box::new(RecordedTraceTaskGenerator::new(
+            self.dir.join(TASKS_FILE),
+            self.read_batch,
+        ))
+    }
+}
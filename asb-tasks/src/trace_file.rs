@@ -0,0 +1,284 @@
+//! The portable trace format of recorded tasks.
+//!
+//! A trace directory holds `warmup.trace.gz` and `tasks.trace.gz`, each a gzip stream of:
+//!
+//! - The magic bytes `ASBTRACE`, followed by the format version as a little-endian `u32`.
+//! - One record per epoch until the end of the stream. A record starts with the number of events
+//!   as a `u32`, followed by the events.
+//!
+//! An event is a one-byte opcode followed by its fields. A byte string is its length as a `u32`
+//! followed by the bytes, and all the integers are little-endian.
+//!
+//! | Opcode | Event      | Fields                              |
+//! |--------|------------|-------------------------------------|
+//! | 0      | `Read`     | key                                 |
+//! | 1      | `ReadMany` | number of keys as a `u32`, keys     |
+//! | 2      | `Write`    | key, value                          |
+//! | 3      | `Delete`   | key                                 |
+//! | 4      | `Prove`    | key                                 |
+//! | 5      | `ReadAt`   | epoch as a `u64`, key               |
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+use super::{Event, Events};
+
+const MAGIC: &[u8; 8] = b"ASBTRACE";
+pub const VERSION: u32 = 1;
+
+pub const WARMUP_FILE: &str = "warmup.trace.gz";
+pub const TASKS_FILE: &str = "tasks.trace.gz";
+
+pub struct TraceWriter {
+    encoder: GzEncoder<BufWriter<File>>,
+}
+
+impl TraceWriter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        encoder.write_all(MAGIC)?;
+        encoder.write_all(&VERSION.to_le_bytes())?;
+        Ok(Self { encoder })
+    }
+
+    pub fn write_epoch(&mut self, events: &Events) -> io::Result<()> {
+        self.write_len(events.0.len())?;
+        for event in events.0.iter() {
+            match event {
+                Event::Read(key) => {
+                    self.encoder.write_all(&[0])?;
+                    self.write_bytes(key)?;
+                }
+                Event::ReadMany(keys) => {
+                    self.encoder.write_all(&[1])?;
+                    self.write_len(keys.len())?;
+                    for key in keys {
+                        self.write_bytes(key)?;
+                    }
+                }
+                Event::Write(key, value) => {
+                    self.encoder.write_all(&[2])?;
+                    self.write_bytes(key)?;
+                    self.write_bytes(value)?;
+                }
+                Event::Delete(key) => {
+                    self.encoder.write_all(&[3])?;
+                    self.write_bytes(key)?;
+                }
+                Event::Prove(key) => {
+                    self.encoder.write_all(&[4])?;
+                    self.write_bytes(key)?;
+                }
+                Event::ReadAt(epoch, key) => {
+                    self.encoder.write_all(&[5])?;
+                    self.encoder.write_all(&(*epoch as u64).to_le_bytes())?;
+                    self.write_bytes(key)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        self.encoder.finish()?.flush()
+    }
+
+    fn write_len(&mut self, len: usize) -> io::Result<()> {
+        let len = u32::try_from(len).map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+        self.encoder.write_all(&len.to_le_bytes())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_len(bytes.len())?;
+        self.encoder.write_all(bytes)
+    }
+}
+
+/// Reads the epochs of a trace file one by one.
+pub struct TraceReader {
+    decoder: GzDecoder<BufReader<File>>,
+}
+
+impl TraceReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut decoder = GzDecoder::new(BufReader::new(File::open(path)?));
+        let mut magic = [0u8; 8];
+        decoder.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Not a trace file"));
+        }
+        let version = u32::from_le_bytes(read_array(&mut decoder)?);
+        if version != VERSION {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Unsupported trace version {}", version),
+            ));
+        }
+        Ok(Self { decoder })
+    }
+
+    /// Read the next epoch, or `None` at the end of the trace.
+    pub fn read_epoch(&mut self) -> io::Result<Option<Events>> {
+        // The end of the stream is only allowed between epochs.
+        let mut first = [0u8; 1];
+        if self.decoder.read(&mut first)? == 0 {
+            return Ok(None);
+        }
+        let mut rest = [0u8; 3];
+        self.decoder.read_exact(&mut rest)?;
+        let len = u32::from_le_bytes([first[0], rest[0], rest[1], rest[2]]) as usize;
+
+        let mut events = Vec::with_capacity(len);
+        for _ in 0..len {
+            let [opcode] = read_array(&mut self.decoder)?;
+            let event = match opcode {
+                0 => Event::Read(self.read_bytes()?),
+                1 => {
+                    let len = self.read_len()?;
+                    Event::ReadMany(
+                        (0..len)
+                            .map(|_| self.read_bytes())
+                            .collect::<Result<_, _>>()?,
+                    )
+                }
+                2 => Event::Write(self.read_bytes()?, self.read_bytes()?),
+                3 => Event::Delete(self.read_bytes()?),
+                4 => Event::Prove(self.read_bytes()?),
+                5 => {
+                    let epoch = u64::from_le_bytes(read_array(&mut self.decoder)?) as usize;
+                    Event::ReadAt(epoch, self.read_bytes()?)
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Unknown opcode {}", opcode),
+                    ))
+                }
+            };
+            events.push(event);
+        }
+        Ok(Some(Events(events)))
+    }
+
+    fn read_len(&mut self) -> io::Result<usize> {
+        Ok(u32::from_le_bytes(read_array(&mut self.decoder)?) as usize)
+    }
+
+    fn read_bytes(&mut self) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0u8; self.read_len()?];
+        self.decoder.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl Iterator for TraceReader {
+    type Item = Events;
+
+    fn next(&mut self) -> Option<Events> {
+        self.read_epoch()
+            .unwrap_or_else(|e| panic!("Cannot read trace: {}", e))
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("asb-{}-{}.trace.gz", std::process::id(), name))
+    }
+
+    fn epochs() -> Vec<Events> {
+        vec![
+            Events(vec![
+                Event::Read(vec![1]),
+                Event::ReadMany(vec![vec![2], vec![], vec![3, 4]]),
+                Event::Write(vec![5], vec![6; 40]),
+            ]),
+            Events(vec![]),
+            Events(vec![
+                Event::Delete(vec![7]),
+                Event::Prove(vec![8, 9]),
+                Event::ReadAt(u32::MAX as usize + 1, vec![10]),
+            ]),
+        ]
+    }
+
+    // Write `bytes` as a gzip stream, bypassing the trace writer.
+    fn write_raw(path: &Path, bytes: &[u8]) {
+        let mut encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    fn read_raw(path: &Path) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        GzDecoder::new(File::open(path).unwrap())
+            .read_to_end(&mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_path("round-trip");
+        let mut writer = TraceWriter::create(&path).unwrap();
+        for events in epochs().iter() {
+            writer.write_epoch(events).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let reader = TraceReader::open(&path).unwrap();
+        assert_eq!(reader.collect::<Vec<_>>(), epochs());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_bad_header() {
+        let path = temp_path("bad-magic");
+        write_raw(&path, b"NOTTRACE\x01\x00\x00\x00");
+        let error = TraceReader::open(&path).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+
+        let path = temp_path("bad-version");
+        write_raw(&path, &[&MAGIC[..], &(VERSION + 1).to_le_bytes()].concat());
+        let error = TraceReader::open(&path).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_truncation() {
+        let path = temp_path("truncation");
+        let mut writer = TraceWriter::create(&path).unwrap();
+        for events in epochs().iter() {
+            writer.write_epoch(events).unwrap();
+        }
+        writer.finish().unwrap();
+
+        // Cut the last epoch in the middle of its events.
+        let bytes = read_raw(&path);
+        write_raw(&path, &bytes[..bytes.len() - 3]);
+
+        let mut reader = TraceReader::open(&path).unwrap();
+        assert_eq!(reader.read_epoch().unwrap(), epochs().into_iter().next());
+        assert_eq!(reader.read_epoch().unwrap(), Some(Events(vec![])));
+        let error = reader.read_epoch().err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+use asb_options::{Options, ValueSize};
+use rand::prelude::*;
+use std::fs;
+
+use super::real_trace::init_value_sizes;
+
+/// Generates the random values written by the random tasks, with sizes drawn from the value size
+/// distribution.
+pub enum ValueSampler {
+    Fixed(usize),
+    Uniform(usize, usize),
+    Histogram {
+        sizes: Vec<usize>,
+        cumulative_weights: Vec<u64>,
+    },
+}
+
+impl ValueSampler {
+    pub fn new(opts: &Options) -> Self {
+        match &opts.value_size {
+            ValueSize::Fixed(size) => ValueSampler::Fixed(*size),
+            ValueSize::Uniform(min, max) => ValueSampler::Uniform(*min, *max),
+            ValueSize::Histogram(path) => Self::from_histogram(load_histogram(path)),
+            ValueSize::Trace => Self::from_histogram(init_value_sizes(&opts.trace_dir)),
+        }
+    }
+
+    fn from_histogram(histogram: Vec<(usize, u64)>) -> Self {
+        let mut total = 0;
+        let (sizes, cumulative_weights) = histogram
+            .into_iter()
+            .filter(|(size, weight)| *size > 0 && *weight > 0)
+            .map(|(size, weight)| {
+                total += weight;
+                (size, total)
+            })
+            .unzip();
+        if total == 0 {
+            panic!("The value size histogram is empty");
+        }
+        ValueSampler::Histogram {
+            sizes,
+            cumulative_weights,
+        }
+    }
+
+    fn size(&self, random: &mut dyn RngCore) -> usize {
+        match self {
+            ValueSampler::Fixed(size) => *size,
+            ValueSampler::Uniform(min, max) => random.gen_range(*min, *max + 1),
+            ValueSampler::Histogram {
+                sizes,
+                cumulative_weights,
+            } => {
+                let x = random.gen_range(0, *cumulative_weights.last().unwrap());
+                sizes[cumulative_weights.partition_point(|weight| *weight <= x)]
+            }
+        }
+    }
+
+    pub fn value(&self, random: &mut dyn RngCore) -> Vec<u8> {
+        let size = self.size(random);
+        // Generate byte by byte, so a fixed size of 32 gives the same values as
+        // `random.gen::<[u8; 32]>()`.
+        (0..size).map(|_| random.gen::<u8>()).collect()
+    }
+}
+
+// Each line is `size,weight`, the empty lines and the lines starting with `#` are skipped.
+fn load_histogram(path: &str) -> Vec<(usize, u64)> {
+    let content = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Cannot read value size histogram {}: {}", path, e));
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let parse = || -> Option<(usize, u64)> {
+                let (size, weight) = line.split_once(',')?;
+                Some((size.trim().parse().ok()?, weight.trim().parse().ok()?))
+            };
+            parse().unwrap_or_else(|| panic!("Invalid line in {}: {}", path, line))
+        })
+        .collect()
+}
@@ -1,29 +1,168 @@
 use fs_extra::dir::CopyOptions;
 use std::fs;
 
+mod restart;
 mod run;
+mod verify;
 
-use asb_options::{AuthAlgo, Backend, Options, StructOpt};
+use asb_options::{AuthAlgo, Backend, Distribution, Journal, Options, ValueSize};
+use restart::check_restart;
 use run::run_tasks;
+use verify::Verifier;
 
 fn main() {
-    let options: Options = Options::from_args();
+    let options: Options = Options::from_args_with_config();
     if options.stat_mem && !options.no_stat {
         panic!("Stat will introduce memory cost")
     }
     if options.algorithm == AuthAlgo::LMPTS && options.backend != Backend::RocksDB {
         panic!("LMPTs can not change backend")
     }
+    if options.enable_proof && options.algorithm == AuthAlgo::LVMT && options.shards != Some(1) {
+        panic!("LVMT only supports proof generation with one shard")
+    }
+    if options.algorithm == AuthAlgo::LVMT
+        && options.enable_proof
+        && options.lvmt_only_merkle_root()
+    {
+        panic!("Proof generation requires the Merkle tree of LVMT, remove --lvmt-only-merkle-root")
+    }
+    if options.history_epochs.is_some() && options.real_trace {
+        panic!("Historical read is only supported in random tasks")
+    }
+    if let AuthAlgo::MPT(journal, _) = options.algorithm {
+        if options.history_epochs.is_some() && journal != Journal::Archive {
+            panic!("Historical read on MPT requires the archive journal, use -a mpt:archive")
+        }
+    }
+    if options.history_epochs.is_some() && options.prove_ratio.is_some() {
+        panic!("Historical read and proof requests can not be mixed")
+    }
+    if options.verify_against.is_some() && options.warmup_from.is_some() {
+        panic!("The reference store of --verify-against can not load a warmup")
+    }
+    if let Some(restart_at) = options.restart_at {
+        if options
+            .max_epoch
+            .map_or(true, |max_epoch| max_epoch <= restart_at)
+        {
+            panic!("Restart check requires --max-epoch larger than the restart epoch")
+        }
+        if options.backend == Backend::InMemoryDB || options.algorithm == AuthAlgo::LMPTS {
+            panic!("Restart check requires a persistent database")
+        }
+        if options.warmup_from.is_some() || options.warmup_to.is_some() {
+            panic!("Restart check does not load or save a warmup")
+        }
+        if options.history_epochs.is_some()
+            || options.prove_ratio.is_some()
+            || options.verify_against.is_some()
+        {
+            panic!("Restart check only supports reads and writes")
+        }
+    }
+    if options.distribution != Distribution::Uniform && options.real_trace {
+        panic!("Key distributions are only supported in random tasks")
+    }
+    if options.grow_ratio.is_some()
+        && (options.real_trace
+            || options.mix.is_some()
+            || options.history_epochs.is_some()
+            || options.prove_ratio.is_some())
+    {
+        panic!("The growing state can not be combined with real traces, operation mixes, historical read or proof requests")
+    }
+    if options.value_size != ValueSize::Fixed(32) && options.real_trace {
+        panic!("Value sizes are only configurable in random tasks")
+    }
+    if options.blockchain.is_some()
+        && (options.real_trace
+            || options.replay_trace.is_some()
+            || options.mix.is_some()
+            || options.grow_ratio.is_some()
+            || options.history_epochs.is_some()
+            || options.prove_ratio.is_some()
+            || options.reader_threads.is_some())
+    {
+        panic!(
+            "The blockchain task can not be combined with other task types or concurrent readers"
+        )
+    }
+    if options.mix.is_some()
+        && (options.real_trace || options.history_epochs.is_some() || options.prove_ratio.is_some())
+    {
+        panic!("The operation mix can not be combined with real traces, historical read or proof requests")
+    }
+    if options.replay_trace.is_some()
+        && (options.real_trace
+            || options.mix.is_some()
+            || options.grow_ratio.is_some()
+            || options.history_epochs.is_some()
+            || options.prove_ratio.is_some()
+            || options.reader_threads.is_some())
+    {
+        panic!("Replayed traces can not be combined with other task types or concurrent readers")
+    }
+    if options.record_trace.is_some()
+        && options.max_epoch.is_none()
+        && !options.real_trace
+        && options.replay_trace.is_none()
+    {
+        panic!("Recording random tasks requires --max-epoch")
+    }
+    if options.reader_threads.is_some() && options.real_trace {
+        panic!("Concurrent readers are only supported in random tasks")
+    }
+    if options.mdbx_growth_step == 0 {
+        panic!("The growth step of MDBX must be positive")
+    }
+    if options.read_batch == Some(0) {
+        panic!("The read batch size must be positive")
+    }
+    if options.read_batch.is_some()
+        && (options.history_epochs.is_some() || options.prove_ratio.is_some())
+    {
+        panic!("Batched reads can not be mixed with historical read or proof requests")
+    }
+    if options.prove_ratio.is_some() {
+        if options.real_trace {
+            panic!("Proof requests are only supported in random tasks")
+        }
+        match options.algorithm {
+            AuthAlgo::RAW | AuthAlgo::LMPTS => {
+                panic!("{:?} does not support proof", options.algorithm)
+            }
+            AuthAlgo::LVMT if !options.enable_proof => {
+                panic!("Proof requests on LVMT require --enable-proof")
+            }
+            AuthAlgo::AMT(_) if options.shards != Some(1) => {
+                panic!("AMT only supports proof generation with one shard")
+            }
+            _ => {}
+        }
+    }
     println!(
         "Testing {:?} with {}",
         options.algorithm,
         if options.real_trace {
             "real trace".into()
+        } else if let Some(ref dir) = options.replay_trace {
+            format!("trace {}", dir)
+        } else if let Some(ref chain) = options.blockchain {
+            format!("{:e} blockchain keys", chain.total_keys())
         } else {
             format!("{:e} addresses", options.total_keys)
         }
     );
 
+    if let Some(ref dir) = options.record_trace {
+        let tasks = asb_tasks::tasks(&options);
+        let epochs = options.max_epoch.unwrap_or(usize::MAX);
+        asb_tasks::record_trace(&*tasks, dir, epochs, !options.no_warmup);
+        println!("Trace recorded to {}", dir);
+        return;
+    }
+
     let db_dir = &options.db_dir;
     let _ = fs::remove_dir_all(db_dir);
     fs::create_dir_all(db_dir).unwrap();
@@ -40,7 +179,18 @@ fn main() {
     }
 
     let tasks = asb_tasks::tasks(&options);
-    let backend = asb_backend::backend(&options);
-    let (db, reporter) = asb_authdb::new(backend, &options);
-    run_tasks(db, tasks, reporter, &options);
+    if let Some(restart_at) = options.restart_at {
+        check_restart(tasks, restart_at, &options);
+        return;
+    }
+    let (backend, batch_get) = asb_backend::backend_with_batch_get(&options);
+    let (db, reporter) = asb_authdb::new(backend, batch_get, &options);
+    if options.history_epochs.is_some() && !db.support_history() {
+        panic!("{:?} does not support historical read", options.algorithm)
+    }
+    let verifier = options
+        .verify_against
+        .as_ref()
+        .map(|algorithm| Verifier::new(algorithm.clone(), &options));
+    run_tasks(db, tasks, reporter, verifier, &options);
 }
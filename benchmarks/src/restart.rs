@@ -0,0 +1,95 @@
+use asb_options::Options;
+use asb_tasks::{Event, Events, TaskTrait};
+use authdb::{AuthDB, StateRoot};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::run::warmup;
+
+fn open(opts: &Options) -> Box<dyn AuthDB> {
+    let (backend, batch_get) = asb_backend::backend_with_batch_get(opts);
+    asb_authdb::new(backend, batch_get, opts).0
+}
+
+// Apply the events of an epoch, and return the root together with a digest of the read results.
+fn run_epoch(db: &mut dyn AuthDB, epoch: usize, events: Events) -> (StateRoot, u64) {
+    let mut hasher = DefaultHasher::new();
+    for event in events.0.into_iter() {
+        match event {
+            Event::Read(key) => db.get(key).hash(&mut hasher),
+            Event::ReadMany(keys) => db.get_many(&keys).hash(&mut hasher),
+            Event::Write(key, value) => db.set(key, value),
+            Event::Delete(key) => db.delete(key),
+            Event::Prove(_) | Event::ReadAt(_, _) => {
+                unreachable!("Restart check only supports reads and writes")
+            }
+        }
+    }
+    (db.commit(epoch), hasher.finish())
+}
+
+fn fresh_options(opts: &Options, name: &str) -> Options {
+    let mut opts = opts.clone();
+    opts.db_dir = format!("{}/{}", opts.db_dir, name);
+    let _ = fs::remove_dir_all(&opts.db_dir);
+    fs::create_dir_all(&opts.db_dir).unwrap();
+    opts
+}
+
+/// Run the tasks without interruption, then run them again with the database dropped and
+/// reopened after `restart_at` epochs, and check that the roots and the read results match.
+pub fn check_restart(tasks: Arc<dyn TaskTrait>, restart_at: usize, opts: &Options) {
+    let total_epochs = opts.max_epoch.unwrap();
+
+    println!("Run {} epochs without restart", total_epochs);
+    let expected: Vec<(StateRoot, u64)> = {
+        let opts = fresh_options(opts, "uninterrupted");
+        let mut db = open(&opts);
+        if !opts.no_warmup {
+            warmup(&mut *db, tasks.warmup(), &mut None, &opts);
+        }
+        tasks
+            .tasks()
+            .take(total_epochs)
+            .enumerate()
+            .map(|(epoch, events)| run_epoch(&mut *db, epoch, events))
+            .collect()
+    };
+
+    println!(
+        "Run {} epochs with restart at epoch {}",
+        total_epochs, restart_at
+    );
+    let opts = fresh_options(opts, "restarted");
+    let mut db = open(&opts);
+    if !opts.no_warmup {
+        warmup(&mut *db, tasks.warmup(), &mut None, &opts);
+    }
+    for (epoch, events) in tasks.tasks().take(total_epochs).enumerate() {
+        if epoch == restart_at {
+            db.flush_all();
+            // The backend must be closed before it is opened again.
+            drop(db);
+            db = open(&opts);
+            println!("Reopened at epoch {}", epoch);
+        }
+
+        let (root, digest) = run_epoch(&mut *db, epoch, events);
+        let (expected_root, expected_digest) = &expected[epoch];
+        if root != *expected_root {
+            panic!(
+                "Root diverges at epoch {}: expected {}, got {}",
+                epoch, expected_root, root
+            );
+        }
+        if digest != *expected_digest {
+            panic!("Read results diverge at epoch {}", epoch);
+        }
+    }
+    println!(
+        "Roots of {} epochs are reproduced after restart",
+        expected.len()
+    );
+}
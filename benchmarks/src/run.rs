@@ -1,24 +1,55 @@
 use asb_options::Options;
-use asb_profile::{Profiler, Reporter};
+use asb_profile::{Op, Profiler, ReaderStat, Reporter};
 use asb_tasks::{Event, Events, TaskTrait};
-use authdb::AuthDB;
+use authdb::{AuthDB, HistoryError, StateRoot};
 use fs_extra::dir::CopyOptions;
 use kvdb::IoStatsKind;
-use std::fs;
+use std::fs::{self, File};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::thread::sleep;
+use std::thread::{sleep, JoinHandle};
 use std::time::{Duration, Instant};
 
-fn warmup(db: &mut dyn AuthDB, tasks: Box<dyn Iterator<Item = Events> + '_>, opts: &Options) {
+use crate::verify::Verifier;
+
+fn print_root(epoch: usize, root: &StateRoot, opts: &Options) {
+    if opts.print_root && epoch % opts.report_epoch == 0 {
+        println!("Root {}", root);
+    }
+}
+
+pub(crate) fn warmup(
+    db: &mut dyn AuthDB,
+    tasks: Box<dyn Iterator<Item = Events> + '_>,
+    verifier: &mut Option<Verifier>,
+    opts: &Options,
+) {
     let time = Instant::now();
 
     for (epoch, events) in tasks.enumerate() {
         for event in events.0.into_iter() {
-            if let Event::Write(key, value) = event {
-                db.set(key, value);
+            match event {
+                Event::Write(key, value) => {
+                    if let Some(verifier) = verifier {
+                        verifier.set(&key, &value);
+                    }
+                    db.set(key, value)
+                }
+                Event::Delete(key) => {
+                    if let Some(verifier) = verifier {
+                        verifier.delete(&key);
+                    }
+                    db.delete(key)
+                }
+                _ => {}
             }
         }
-        db.commit(epoch);
+        if let Some(verifier) = verifier {
+            verifier.commit(epoch);
+        }
+        let root = db.commit(epoch);
+        print_root(epoch, &root, opts);
         if (epoch + 1) % opts.report_epoch == 0 {
             println!(
                 "Time {:>7.3?}s, Warming up epoch: {:>5}",
@@ -34,16 +65,81 @@ fn warmup(db: &mut dyn AuthDB, tasks: Box<dyn Iterator<Item = Events> + '_>, opt
     }
 }
 
+// A batch of `ops` operations is recorded as `ops` operations of the average latency.
+fn notify_latency(reporter: &mut Reporter, op: Op, start: Instant, ops: usize, opts: &Options) {
+    if opts.no_stat || ops == 0 {
+        return;
+    }
+    let average = start.elapsed() / ops as u32;
+    for _ in 0..ops {
+        reporter.notify_latency(op, average);
+    }
+}
+
+// Prove `keys` again after the commit of their epoch and verify the proofs against `root`. Only
+// the verification is timed, and the whole check is excluded from the per-operation time.
+fn verify_proofs(
+    db: &mut dyn AuthDB,
+    root: &StateRoot,
+    keys: Vec<Vec<u8>>,
+    reporter: &mut Reporter,
+) {
+    let start = Instant::now();
+    let proof_root = root.proof_root().expect("A state with proofs has a root");
+    for key in keys {
+        // The failed proof requests are counted in the tasks.
+        let proof = match db.prove(key.clone()) {
+            Ok(proof) => proof,
+            Err(_) => continue,
+        };
+        let time = Instant::now();
+        let proven = db.verify(key.clone(), &proof, proof_root);
+        let elapsed = time.elapsed();
+        reporter.notify_verify(elapsed, proven.ok() == Some(db.get(key)));
+    }
+    reporter.notify_unmeasured(start.elapsed());
+}
+
+// Each reader thread reads random keys through a read-only handle until `stop` is set.
+fn spawn_readers(
+    db: &dyn AuthDB,
+    stop: &Arc<AtomicBool>,
+    opts: &Options,
+) -> Vec<(JoinHandle<()>, Arc<ReaderStat>)> {
+    (0..opts.reader_threads.unwrap_or(0))
+        .map(|thread| {
+            let reader = db.reader().unwrap_or_else(|| {
+                panic!("{:?} does not support concurrent readers", opts.algorithm)
+            });
+            let keys = asb_tasks::reader_keys(opts, thread);
+            let stat = Arc::new(ReaderStat::default());
+            let (stop, thread_stat) = (stop.clone(), stat.clone());
+            let handle = std::thread::spawn(move || {
+                for key in keys {
+                    if stop.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let time = Instant::now();
+                    let _ = reader.get(key);
+                    thread_stat.record(time.elapsed());
+                }
+            });
+            (handle, stat)
+        })
+        .collect()
+}
+
 pub fn run_tasks(
     mut db: Box<dyn AuthDB>,
     // _backend_any: Arc<dyn Any>,
     tasks: Arc<dyn TaskTrait>,
     mut reporter: Reporter,
+    mut verifier: Option<Verifier>,
     opts: &Options,
 ) {
     println!("Start warming up");
     if opts.warmup_from.is_none() && !opts.no_warmup {
-        warmup(&mut *db, tasks.warmup(), opts);
+        warmup(&mut *db, tasks.warmup(), &mut verifier, opts);
         if let Some(ref warmup_dir) = opts.warmup_to() {
             println!("Waiting for post ops");
 
@@ -76,6 +172,15 @@ pub fn run_tasks(
     }
     println!("Warm up done");
 
+    let mut root_log = opts
+        .roots_to
+        .as_ref()
+        .map(|path| File::create(path).unwrap());
+
+    let stop_readers = Arc::new(AtomicBool::new(false));
+    let readers = spawn_readers(&*db, &stop_readers, opts);
+    reporter.set_readers(readers.iter().map(|(_, stat)| stat.clone()).collect());
+
     let frequency = if opts.report_dir.is_none() { -1 } else { 250 };
     let mut profiler = Profiler::new(frequency);
     reporter.start();
@@ -94,26 +199,119 @@ pub fn run_tasks(
 
         let mut read_count = 0;
         let mut write_count = 0;
+        let mut proved_keys = Vec::new();
 
         for event in events.0.into_iter() {
             match event {
                 Event::Read(key) => {
                     read_count += 1;
+                    let expected = verifier.as_ref().map(|v| (key.clone(), v.get(&key)));
+                    let time = Instant::now();
                     let ans = db.get(key);
+                    notify_latency(&mut reporter, Op::Get, time, 1, opts);
                     if ans.is_none() {
                         reporter.notify_empty_read();
                     }
+                    if let Some((key, expected)) = expected {
+                        verifier.as_ref().unwrap().check(&key, &expected, &ans);
+                    }
+                }
+                Event::ReadMany(keys) => {
+                    read_count += keys.len();
+                    let time = Instant::now();
+                    let answers = db.get_many(&keys);
+                    notify_latency(&mut reporter, Op::Get, time, keys.len(), opts);
+                    for ans in answers.iter() {
+                        if ans.is_none() {
+                            reporter.notify_empty_read();
+                        }
+                    }
+                    if let Some(verifier) = &verifier {
+                        for (key, ans) in keys.iter().zip(answers.iter()) {
+                            verifier.check(key, &verifier.get(key), ans);
+                        }
+                    }
                 }
                 Event::Write(key, value) => {
                     write_count += 1;
+                    if let Some(verifier) = &mut verifier {
+                        verifier.set(&key, &value);
+                    }
+                    let time = Instant::now();
                     db.set(key, value);
+                    notify_latency(&mut reporter, Op::Set, time, 1, opts);
+                }
+                Event::Delete(key) => {
+                    write_count += 1;
+                    if let Some(verifier) = &mut verifier {
+                        verifier.delete(&key);
+                    }
+                    let time = Instant::now();
+                    db.delete(key);
+                    notify_latency(&mut reporter, Op::Set, time, 1, opts);
+                }
+                Event::ReadAt(read_epoch, key) => {
+                    read_count += 1;
+                    let expected = verifier
+                        .as_ref()
+                        .and_then(|v| v.get_at(read_epoch, &key))
+                        .map(|expected| (key.clone(), expected));
+                    let time = Instant::now();
+                    let ans = db.get_at(read_epoch, key);
+                    notify_latency(&mut reporter, Op::Get, time, 1, opts);
+                    let ans = match ans {
+                        Ok(ans) => ans,
+                        // E.g. the epochs committed before loading the warmup.
+                        Err(HistoryError::Unavailable(_)) => {
+                            reporter.notify_empty_read();
+                            continue;
+                        }
+                        Err(e) => panic!("Historical read at epoch {} fails: {:?}", read_epoch, e),
+                    };
+                    if ans.is_none() {
+                        reporter.notify_empty_read();
+                    }
+                    if let Some((key, expected)) = expected {
+                        verifier.as_ref().unwrap().check(&key, &expected, &ans);
+                    }
+                }
+                Event::Prove(key) => {
+                    if opts.verify_all_proofs || proved_keys.is_empty() {
+                        proved_keys.push(key.clone());
+                    }
+                    let time = Instant::now();
+                    match db.prove(key) {
+                        Ok(proof) => reporter.notify_prove(time.elapsed(), proof.len()),
+                        Err(_) => reporter.notify_prove_error(),
+                    }
                 }
             }
         }
-        db.commit(epoch);
+        if let Some(verifier) = &mut verifier {
+            verifier.commit(epoch);
+        }
+        let time = Instant::now();
+        let root = db.commit(epoch);
+        notify_latency(&mut reporter, Op::Commit, time, 1, opts);
+        print_root(epoch, &root, opts);
+        if let Some(file) = &mut root_log {
+            writeln!(file, "{},{}", epoch, root).unwrap();
+        }
+        if !proved_keys.is_empty() {
+            verify_proofs(&mut *db, &root, proved_keys, &mut reporter);
+        }
 
+        if let Some(keys) = tasks.num_keys(epoch + 1) {
+            reporter.notify_state_size(keys);
+        }
         reporter.notify_epoch(epoch, read_count, write_count, &*db, opts);
     }
 
+    stop_readers.store(true, Ordering::Relaxed);
+    for (handle, _) in readers {
+        handle.join().unwrap();
+    }
+
+    reporter.finish();
     reporter.collect_profiling(profiler);
 }
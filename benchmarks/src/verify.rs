@@ -0,0 +1,59 @@
+use asb_options::{AuthAlgo, Options};
+use authdb::AuthDB;
+
+/// Runs a reference store in lockstep with the store under test, and panics at the first read
+/// where the two stores disagree.
+pub struct Verifier {
+    algorithm: AuthAlgo,
+    reference: Box<dyn AuthDB>,
+    epoch: usize,
+}
+
+impl Verifier {
+    pub fn new(algorithm: AuthAlgo, opts: &Options) -> Self {
+        let reference = asb_authdb::new_reference(&algorithm, opts);
+        Self {
+            algorithm,
+            reference,
+            epoch: 0,
+        }
+    }
+
+    pub fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.reference.set(key.to_vec(), value.to_vec());
+    }
+
+    pub fn delete(&mut self, key: &[u8]) {
+        self.reference.delete(key.to_vec());
+    }
+
+    pub fn commit(&mut self, epoch: usize) {
+        self.reference.commit(epoch);
+        self.epoch = epoch + 1;
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<Box<[u8]>> {
+        self.reference.get(key.to_vec())
+    }
+
+    pub fn get_at(&self, epoch: usize, key: &[u8]) -> Option<Option<Box<[u8]>>> {
+        self.reference.get_at(epoch, key.to_vec()).ok()
+    }
+
+    pub fn check(&self, key: &[u8], expected: &Option<Box<[u8]>>, actual: &Option<Box<[u8]>>) {
+        if expected != actual {
+            panic!(
+                "Read diverges from {:?} at epoch {}, key {}: expected {:?}, got {:?}",
+                self.algorithm,
+                self.epoch,
+                hex(key),
+                expected.as_deref().map(hex),
+                actual.as_deref().map(hex),
+            )
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}